@@ -0,0 +1,434 @@
+//! Fixed-capacity inversion maps and lists that store their ranges inline and never allocate.
+//!
+//! Operations that would need more than `N` entries fail with a [`CapacityError`] and leave the
+//! collection untouched.
+
+use core::fmt;
+use core::iter::FusedIterator;
+use core::ops::{Not, RangeBounds, RangeInclusive};
+
+use crate::raw::{self, Entry};
+use crate::util::{bounds_to_range, predecessor, successor};
+use crate::OrderedIndex;
+
+pub use crate::raw::{EntriesRef, Iter};
+
+use self::vec::ArrayVec;
+
+#[cfg(test)]
+mod test;
+
+mod vec;
+
+/// The error returned when an operation would need more entries than the capacity allows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityError;
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("inversion map capacity exceeded")
+    }
+}
+
+impl core::error::Error for CapacityError {}
+
+/// An [`InversionMap`](crate::InversionMap) that holds at most `N` entries inline.
+#[derive(Clone, Debug)]
+pub struct ArrayInversionMap<Idx, V, const N: usize> {
+    ranges: ArrayVec<Entry<Idx, V>, N>,
+}
+
+impl<Idx, V, const N: usize> ArrayInversionMap<Idx, V, N> {
+    pub const fn new() -> Self {
+        ArrayInversionMap {
+            ranges: ArrayVec::new(),
+        }
+    }
+
+    /// Returns the maximum number of entries this map can hold, that is `N`.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<Idx, V, const N: usize> Default for ArrayInversionMap<Idx, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Idx: PartialEq, V: PartialEq, const N: usize> PartialEq for ArrayInversionMap<Idx, V, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ranges.as_slice() == other.ranges.as_slice()
+    }
+}
+
+impl<Idx: Eq, V: Eq, const N: usize> Eq for ArrayInversionMap<Idx, V, N> {}
+
+// region: delegate methods
+impl<Idx: OrderedIndex, V, const N: usize> ArrayInversionMap<Idx, V, N> {
+    #[inline]
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.ranges.as_slice().len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.as_slice().is_empty()
+    }
+
+    #[inline]
+    pub fn start(&self) -> Option<Idx> {
+        self.ranges.as_slice().first().map(|r| r.start)
+    }
+
+    /// Returns the last index covered by the map, this is an inclusive bound.
+    #[inline]
+    pub fn end(&self) -> Option<Idx> {
+        self.ranges.as_slice().last().map(|r| r.end)
+    }
+
+    #[inline]
+    pub fn first(&self) -> Option<(RangeInclusive<Idx>, &V)> {
+        self.ranges
+            .as_slice()
+            .first()
+            .map(|r| (r.range(), &r.value))
+    }
+
+    #[inline]
+    pub fn last(&self) -> Option<(RangeInclusive<Idx>, &V)> {
+        self.ranges.as_slice().last().map(|r| (r.range(), &r.value))
+    }
+}
+// endregion
+
+impl<Idx: OrderedIndex, V, const N: usize> ArrayInversionMap<Idx, V, N> {
+    /// Checks whether the given index is contained in the map.
+    pub fn contains(&self, index: Idx) -> bool {
+        raw::binary_search(self.ranges.as_slice(), index).is_ok()
+    }
+
+    /// Checks whether a single range of the map fully contains the given range.
+    ///
+    /// Empty ranges are never contained.
+    pub fn contains_range<R: RangeBounds<Idx>>(&self, range: R) -> bool {
+        bounds_to_range(range)
+            .is_some_and(|range| raw::contains_range(self.ranges.as_slice(), range))
+    }
+
+    /// Looks up the range and value associated with the given index.
+    pub fn lookup(&self, index: Idx) -> Option<(RangeInclusive<Idx>, &V)> {
+        let entries = self.ranges.as_slice();
+        raw::binary_search(entries, index)
+            .map(|idx| (entries[idx].range(), &entries[idx].value))
+            .ok()
+    }
+
+    /// Looks up all entries whose ranges overlap with the given range.
+    pub fn lookup_range<R: RangeBounds<Idx>>(&self, range: R) -> Option<EntriesRef<'_, Idx, V>> {
+        let range = bounds_to_range(range)?;
+        let entries = self.ranges.as_slice();
+        let slice = &entries[raw::overlapping(entries, range)];
+        slice.is_empty().not().then_some(EntriesRef { slice })
+    }
+
+    /// Check if the given range intersects with any ranges inside of the map.
+    pub fn intersects<R: RangeBounds<Idx>>(&self, range: R) -> bool {
+        bounds_to_range(range).is_some_and(|range| raw::intersects(self.ranges.as_slice(), range))
+    }
+
+    /// Returns the complete surrounding range, if any.
+    pub fn span(&self) -> Option<RangeInclusive<Idx>> {
+        Some(self.start()?..=self.end()?)
+    }
+
+    /// Returns the number of indices covered by the map, saturating at `u128::MAX`.
+    pub fn count(&self) -> u128 {
+        raw::count(self.ranges.as_slice())
+    }
+
+    /// An iterator over the ranges and values contained in this map.
+    pub fn iter(&self) -> Iter<'_, Idx, V> {
+        Iter::new(self.ranges.as_slice())
+    }
+}
+
+impl<Idx: OrderedIndex, V: Clone, const N: usize> ArrayInversionMap<Idx, V, N> {
+    /// See [`InversionMap::insert_unit`](crate::InversionMap::insert_unit).
+    pub fn insert_unit(&mut self, index: Idx, value: V) -> Result<bool, CapacityError> {
+        raw::unit_with(&mut self.ranges, index, value, |ranges, _, value| {
+            raw::insert_range_with(ranges, index..=index, |_| value)
+        })
+    }
+
+    /// See [`InversionMap::insert_range`](crate::InversionMap::insert_range).
+    pub fn insert_range<R: RangeBounds<Idx>>(
+        &mut self,
+        range: R,
+        value: V,
+    ) -> Result<(), CapacityError> {
+        self.insert_range_with(range, |_| value.clone())
+    }
+
+    /// See [`InversionMap::insert_range_with`](crate::InversionMap::insert_range_with).
+    pub fn insert_range_with<R: RangeBounds<Idx>>(
+        &mut self,
+        range: R,
+        value: impl FnOnce(EntriesRef<'_, Idx, V>) -> V,
+    ) -> Result<(), CapacityError> {
+        match bounds_to_range(range) {
+            Some(range) => raw::insert_range_with(&mut self.ranges, range, value),
+            None => Ok(()),
+        }
+    }
+
+    /// See [`InversionMap::remove_range`](crate::InversionMap::remove_range).
+    pub fn remove_range<R: RangeBounds<Idx>>(
+        &mut self,
+        range: R,
+        split_boundary_left: impl FnOnce(RangeInclusive<Idx>, &V) -> V,
+        split_boundary_right: impl FnOnce(RangeInclusive<Idx>, &V) -> V,
+    ) -> Result<(), CapacityError> {
+        match bounds_to_range(range) {
+            Some(range) => raw::remove_range(
+                &mut self.ranges,
+                range,
+                split_boundary_left,
+                split_boundary_right,
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// See [`InversionMap::split`](crate::InversionMap::split).
+    pub fn split(&mut self, at: Idx) -> Result<Option<(usize, usize)>, CapacityError> {
+        self.split_with(at, |_, v| (v.clone(), v))
+    }
+
+    /// See [`InversionMap::split_with`](crate::InversionMap::split_with).
+    pub fn split_with(
+        &mut self,
+        at: Idx,
+        splitter: impl FnOnce(RangeInclusive<Idx>, V) -> (V, V),
+    ) -> Result<Option<(usize, usize)>, CapacityError> {
+        raw::split_with(&mut self.ranges, at, splitter)
+    }
+}
+
+impl<Idx: OrderedIndex, V, const N: usize> ArrayInversionMap<Idx, V, N> {
+    /// See [`InversionMap::add_unit`](crate::InversionMap::add_unit).
+    pub fn add_unit(&mut self, index: Idx, value: V) -> Result<bool, CapacityError> {
+        raw::unit_with(&mut self.ranges, index, value, |ranges, idx, value| {
+            ranges.as_mut_slice()[idx].value = value;
+            Ok(())
+        })
+    }
+
+    /// See [`InversionMap::add_range`](crate::InversionMap::add_range).
+    pub fn add_range<R: RangeBounds<Idx>>(
+        &mut self,
+        range: R,
+        value: V,
+    ) -> Result<(), CapacityError> {
+        self.add_range_with(range, |_| value)
+    }
+
+    /// See [`InversionMap::add_range_with`](crate::InversionMap::add_range_with).
+    pub fn add_range_with<R: RangeBounds<Idx>>(
+        &mut self,
+        range: R,
+        value: impl FnOnce(EntriesRef<'_, Idx, V>) -> V,
+    ) -> Result<(), CapacityError> {
+        match bounds_to_range(range) {
+            Some(range) => raw::add_range_with(&mut self.ranges, range, value),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'il, Idx: OrderedIndex, V, const N: usize> IntoIterator for &'il ArrayInversionMap<Idx, V, N> {
+    type Item = (RangeInclusive<Idx>, &'il V);
+    type IntoIter = Iter<'il, Idx, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An [`InversionList`](crate::InversionList) that holds at most `N` ranges inline.
+///
+/// Like [`InversionList`](crate::InversionList), the list never holds directly adjacent ranges, so
+/// two lists are equal exactly if they cover the same indices.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArrayInversionList<Idx: OrderedIndex, const N: usize>(ArrayInversionMap<Idx, (), N>);
+
+impl<Idx: OrderedIndex, const N: usize> ArrayInversionList<Idx, N> {
+    pub const fn new() -> Self {
+        ArrayInversionList(ArrayInversionMap::new())
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn start(&self) -> Option<Idx> {
+        self.0.start()
+    }
+
+    /// Returns the last index covered by the list, this is an inclusive bound.
+    pub fn end(&self) -> Option<Idx> {
+        self.0.end()
+    }
+
+    /// Returns the complete surrounding range, if any.
+    pub fn span(&self) -> Option<RangeInclusive<Idx>> {
+        self.0.span()
+    }
+
+    /// Returns the number of indices covered by the list, saturating at `u128::MAX`.
+    pub fn count(&self) -> u128 {
+        self.0.count()
+    }
+
+    pub fn first(&self) -> Option<RangeInclusive<Idx>> {
+        self.0.first().map(|(range, _)| range)
+    }
+
+    pub fn last(&self) -> Option<RangeInclusive<Idx>> {
+        self.0.last().map(|(range, _)| range)
+    }
+
+    /// Checks whether the given index is inside any of the contained ranges.
+    pub fn contains(&self, value: Idx) -> bool {
+        self.0.contains(value)
+    }
+
+    /// Checks whether this list contains a range that is a "superrange" of the given range.
+    pub fn contains_range<R: RangeBounds<Idx>>(&self, range: R) -> bool {
+        self.0.contains_range(range)
+    }
+
+    /// Looks up the range the given index is part of if it is contained within the list.
+    pub fn lookup(&self, index: Idx) -> Option<RangeInclusive<Idx>> {
+        self.0.lookup(index).map(|(range, _)| range)
+    }
+
+    /// Looks up all entries whose ranges overlap with the given range.
+    pub fn lookup_range<R: RangeBounds<Idx>>(&self, range: R) -> Option<EntriesRef<'_, Idx, ()>> {
+        self.0.lookup_range(range)
+    }
+
+    /// Check if the given range intersects with any ranges inside of the list.
+    pub fn intersects<R: RangeBounds<Idx>>(&self, range: R) -> bool {
+        self.0.intersects(range)
+    }
+
+    /// Inserts the unit range(index..=index), returning whether `index` was not yet contained.
+    ///
+    /// As the list holds no values this is the same as [`Self::add_unit`].
+    pub fn insert_unit(&mut self, index: Idx) -> Result<bool, CapacityError> {
+        self.add_unit(index)
+    }
+
+    /// Inserts the range, as the list holds no values this is the same as [`Self::add_range`].
+    pub fn insert_range<R: RangeBounds<Idx>>(&mut self, range: R) -> Result<(), CapacityError> {
+        self.add_range(range)
+    }
+
+    pub fn add_unit(&mut self, index: Idx) -> Result<bool, CapacityError> {
+        if self.contains(index) {
+            return Ok(false);
+        }
+        self.0
+            .add_range_with(self.widen(index..=index), |_| ())
+            .map(|()| true)
+    }
+
+    pub fn add_range<R: RangeBounds<Idx>>(&mut self, range: R) -> Result<(), CapacityError> {
+        match bounds_to_range(range) {
+            Some(range) => self.0.add_range_with(self.widen(range), |_| ()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn remove_range<R: RangeBounds<Idx>>(&mut self, range: R) -> Result<(), CapacityError> {
+        // removing can't make two ranges adjacent that weren't before
+        self.0.remove_range(range, |_, _| (), |_, _| ())
+    }
+
+    /// Widens `range` over the ranges directly adjacent to it, so that adding it merges them
+    /// instead of taking up another entry.
+    fn widen(&self, range: RangeInclusive<Idx>) -> RangeInclusive<Idx> {
+        let (mut start, mut end) = range.into_inner();
+        if let Some(prev) = predecessor(start).and_then(|prev| self.lookup(prev)) {
+            start = *prev.start();
+        }
+        if let Some(next) = successor(end).and_then(|next| self.lookup(next)) {
+            end = *next.end();
+        }
+        start..=end
+    }
+
+    /// An iterator over the ranges contained in this list.
+    pub fn iter(&self) -> ListIter<'_, Idx> {
+        ListIter {
+            iter: self.0.iter(),
+        }
+    }
+}
+
+impl<'a, Idx: OrderedIndex, const N: usize> IntoIterator for &'a ArrayInversionList<Idx, N> {
+    type Item = RangeInclusive<Idx>;
+    type IntoIter = ListIter<'a, Idx>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ListIter<'a, Idx: OrderedIndex> {
+    iter: Iter<'a, Idx, ()>,
+}
+
+impl<Idx: OrderedIndex> Iterator for ListIter<'_, Idx> {
+    type Item = RangeInclusive<Idx>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, ())| k)
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Idx: OrderedIndex> DoubleEndedIterator for ListIter<'_, Idx> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(k, ())| k)
+    }
+}
+
+impl<Idx: OrderedIndex> FusedIterator for ListIter<'_, Idx> {}
+impl<Idx: OrderedIndex> ExactSizeIterator for ListIter<'_, Idx> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
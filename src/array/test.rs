@@ -0,0 +1,106 @@
+use super::*;
+
+#[test]
+fn insert_range_capacity() {
+    let mut am = ArrayInversionMap::<u8, u8, 3>::new();
+    am.insert_range(0..=255, 0).unwrap();
+    // splitting the one range into three fits exactly
+    am.insert_range(10..20, 1).unwrap();
+    assert_eq!(
+        am.iter().collect::<Vec<_>>(),
+        [(0..=9, &0), (10..=19, &1), (20..=255, &0)]
+    );
+    // this would need five entries
+    assert_eq!(am.insert_range(100..110, 2), Err(CapacityError));
+    assert_eq!(am.len(), 3);
+    assert_eq!(am.lookup(105), Some((20..=255, &0)));
+    // overwriting the whole middle does not need any extra room
+    am.insert_range(5..=30, 3).unwrap();
+    assert_eq!(
+        am.iter().collect::<Vec<_>>(),
+        [(0..=4, &0), (5..=30, &3), (31..=255, &0)]
+    );
+}
+
+#[test]
+fn add_range_merges_without_room() {
+    let mut am = ArrayInversionMap::<u32, u8, 2>::new();
+    am.add_range(0..10, 0).unwrap();
+    am.add_range(20..30, 0).unwrap();
+    assert_eq!(am.add_range(40..50, 0), Err(CapacityError));
+    am.add_range(5..25, 1).unwrap();
+    assert_eq!(am.iter().collect::<Vec<_>>(), [(0..=29, &1)]);
+    assert!(am.contains_range(3..27));
+    assert!(am.intersects(29..40));
+    assert_eq!(am.lookup_range(0..100).unwrap().len(), 1);
+}
+
+#[test]
+fn remove_and_split() {
+    let mut am = ArrayInversionMap::<u32, u8, 2>::new();
+    am.insert_range(0..100, 0).unwrap();
+    assert_eq!(am.split(50), Ok(Some((0, 1))));
+    assert_eq!(am.split(75), Err(CapacityError));
+    assert_eq!(
+        am.remove_range(60..70, |_, v| *v, |_, v| *v),
+        Err(CapacityError)
+    );
+    am.remove_range(40..60, |_, _| 1, |_, _| 2).unwrap();
+    assert_eq!(am.iter().collect::<Vec<_>>(), [(0..=39, &1), (60..=99, &2)]);
+    am.clear();
+    assert!(am.is_empty());
+}
+
+#[test]
+fn list() {
+    let mut al = ArrayInversionList::<u8, 2>::new();
+    assert_eq!(al.insert_unit(255), Ok(true));
+    assert_eq!(al.insert_unit(255), Ok(false));
+    al.insert_range(0..10).unwrap();
+    assert_eq!(al.insert_range(20..30), Err(CapacityError));
+    assert_eq!(al.iter().collect::<Vec<_>>(), [0..=9, 255..=255]);
+    assert!(al.iter().rev().eq([255..=255, 0..=9]));
+    assert_eq!(al.span(), Some(0..=255));
+    assert_eq!(al.count(), 11);
+    al.remove_range(..5).unwrap();
+    assert_eq!(al.first(), Some(5..=9));
+}
+
+#[test]
+fn list_stays_canonical() {
+    let mut al = ArrayInversionList::<u8, 2>::new();
+    al.insert_range(0..10).unwrap();
+    al.insert_range(20..30).unwrap();
+    // filling the gap merges everything into one range instead of needing a third entry
+    assert_eq!(al.insert_range(10..20), Ok(()));
+    assert_eq!(al.iter().collect::<Vec<_>>(), [0..=29]);
+    assert_eq!(al.add_unit(30), Ok(true));
+    assert_eq!(al.insert_unit(15), Ok(false));
+    assert_eq!(al.iter().collect::<Vec<_>>(), [0..=30]);
+
+    // equality compares the covered indices, whatever order they were added in
+    let mut other = ArrayInversionList::<u8, 2>::new();
+    for i in (0..=30).rev() {
+        other.add_unit(i).unwrap();
+    }
+    assert_eq!(al, other);
+    other.remove_range(5..=5).unwrap();
+    assert_ne!(al, other);
+}
+
+#[test]
+fn drops_values() {
+    use std::rc::Rc;
+    let value = Rc::new(());
+    let mut am = ArrayInversionMap::<u8, Rc<()>, 4>::new();
+    am.insert_range(0..10, value.clone()).unwrap();
+    am.insert_range(3..5, value.clone()).unwrap();
+    assert_eq!(Rc::strong_count(&value), 4);
+    let clone = am.clone();
+    assert_eq!(Rc::strong_count(&value), 7);
+    am.insert_range(.., value.clone()).unwrap();
+    assert_eq!(Rc::strong_count(&value), 5);
+    drop(am);
+    drop(clone);
+    assert_eq!(Rc::strong_count(&value), 1);
+}
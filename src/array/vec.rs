@@ -0,0 +1,111 @@
+use core::mem::MaybeUninit;
+use core::ops::Range;
+use core::{fmt, slice};
+
+use crate::array::CapacityError;
+use crate::raw::{Entry, Storage};
+
+/// A vector with inline storage for up to `N` elements.
+pub(crate) struct ArrayVec<T, const N: usize> {
+    buf: [MaybeUninit<T>; N],
+    // invariant: the first `len` elements of `buf` are initialized
+    len: usize,
+}
+
+impl<T, const N: usize> ArrayVec<T, N> {
+    pub(crate) const fn new() -> Self {
+        ArrayVec {
+            buf: [const { MaybeUninit::uninit() }; N],
+            len: 0,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn as_slice(&self) -> &[T] {
+        // SAFETY: the first `len` elements are initialized
+        unsafe { slice::from_raw_parts(self.buf.as_ptr().cast::<T>(), self.len) }
+    }
+
+    #[inline]
+    pub(crate) fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: the first `len` elements are initialized
+        unsafe { slice::from_raw_parts_mut(self.buf.as_mut_ptr().cast::<T>(), self.len) }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.splice(0..self.len, []);
+    }
+
+    /// Replaces the elements in `range` with `with`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds or `with` does not fit.
+    pub(crate) fn splice(&mut self, range: Range<usize>, with: impl IntoIterator<Item = T>) {
+        let len = self.len;
+        assert!(range.start <= range.end && range.end <= len);
+        // forget about the tail while dropping, so that a panicking drop only leaks
+        self.len = range.start;
+        for slot in &mut self.buf[range.clone()] {
+            // SAFETY: `range` lies within the first `len` elements and each one is dropped once
+            unsafe { slot.assume_init_drop() };
+        }
+        self.buf[range.start..len].rotate_left(range.len());
+        self.len = len - range.len();
+
+        for (at, item) in (range.start..).zip(with) {
+            assert!(self.len < N, "ArrayVec capacity exceeded");
+            self.buf[self.len].write(item);
+            self.buf[at..=self.len].rotate_right(1);
+            self.len += 1;
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayVec<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for ArrayVec<T, N> {
+    fn clone(&self) -> Self {
+        let mut res = ArrayVec::new();
+        res.splice(0..0, self.as_slice().iter().cloned());
+        res
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for ArrayVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl<Idx, V, const N: usize> Storage<Idx, V> for ArrayVec<Entry<Idx, V>, N> {
+    type Error = CapacityError;
+
+    #[inline]
+    fn entries(&self) -> &[Entry<Idx, V>] {
+        self.as_slice()
+    }
+
+    #[inline]
+    fn entries_mut(&mut self) -> &mut [Entry<Idx, V>] {
+        self.as_mut_slice()
+    }
+
+    #[inline]
+    fn reserve(&mut self, removed: usize, inserted: usize) -> Result<(), Self::Error> {
+        if self.len - removed + inserted <= N {
+            Ok(())
+        } else {
+            Err(CapacityError)
+        }
+    }
+
+    #[inline]
+    fn splice(&mut self, range: Range<usize>, with: impl IntoIterator<Item = Entry<Idx, V>>) {
+        ArrayVec::splice(self, range, with);
+    }
+}
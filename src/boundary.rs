@@ -0,0 +1,439 @@
+use core::iter::{FromIterator, FusedIterator};
+use core::ops::{self, Range, RangeBounds, RangeInclusive};
+
+use alloc::vec::Vec;
+
+use crate::map::Entry;
+use crate::util::{bounds_to_range, range_from_chunk, successor};
+use crate::{InversionList, InversionMap, OrderedIndex};
+
+#[cfg(test)]
+mod test;
+
+/// A set of non-overlapping ranges stored as the classic inversion list, a single sorted vector
+/// of the points at which membership toggles.
+///
+/// Even positions hold the (inclusive) starts of ranges, odd positions the exclusive ends. If the
+/// number of boundaries is odd, the last range extends up to and including
+/// [`OrderedIndex::max_value()`]. Every boundary is only stored once, so adjacent ranges are
+/// always merged and the list is canonical.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BoundaryList<Idx: OrderedIndex = usize> {
+    boundaries: Vec<Idx>,
+}
+
+impl<Idx: OrderedIndex> BoundaryList<Idx> {
+    pub fn new() -> Self {
+        BoundaryList {
+            boundaries: Vec::new(),
+        }
+    }
+
+    /// Creates a list with room for `capacity` ranges.
+    pub fn with_capacity(capacity: usize) -> Self {
+        BoundaryList {
+            boundaries: Vec::with_capacity(capacity * 2),
+        }
+    }
+
+    /// Returns the number of ranges the list can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.boundaries.capacity() / 2
+    }
+
+    pub fn clear(&mut self) {
+        self.boundaries.clear();
+    }
+
+    /// Returns the number of ranges in the list.
+    pub fn len(&self) -> usize {
+        self.boundaries.len().div_ceil(2)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boundaries.is_empty()
+    }
+
+    /// Returns the raw toggle points of this list.
+    pub fn boundaries(&self) -> &[Idx] {
+        &self.boundaries
+    }
+
+    pub fn start(&self) -> Option<Idx> {
+        self.boundaries.first().copied()
+    }
+
+    /// Returns the last index covered by the list, this is an inclusive bound.
+    pub fn end(&self) -> Option<Idx> {
+        self.last().map(|range| *range.end())
+    }
+
+    /// Returns the complete surrounding range, if any.
+    pub fn span(&self) -> Option<RangeInclusive<Idx>> {
+        Some(self.start()?..=self.end()?)
+    }
+
+    pub fn first(&self) -> Option<RangeInclusive<Idx>> {
+        self.range_at(0)
+    }
+
+    pub fn last(&self) -> Option<RangeInclusive<Idx>> {
+        self.range_at(self.len().checked_sub(1)?)
+    }
+
+    /// Checks whether the given index is inside any of the contained ranges.
+    pub fn contains(&self, index: Idx) -> bool {
+        self.position(index) % 2 == 1
+    }
+
+    /// Checks whether this list contains a range that is a "superrange" of the given range.
+    pub fn contains_range<R: RangeBounds<Idx>>(&self, range: R) -> bool {
+        let Some(range) = bounds_to_range(range) else {
+            return false;
+        };
+        self.lookup(*range.start())
+            .is_some_and(|it| it.end() >= range.end())
+    }
+
+    /// Looks up the range the given index is part of if it is contained within the list.
+    pub fn lookup(&self, index: Idx) -> Option<RangeInclusive<Idx>> {
+        let pos = self.position(index);
+        if pos % 2 == 1 {
+            self.range_at(pos / 2)
+        } else {
+            None
+        }
+    }
+
+    /// Looks up all ranges that overlap with the given range.
+    pub fn lookup_range<R: RangeBounds<Idx>>(&self, range: R) -> Option<Iter<'_, Idx>> {
+        let (start, end) = bounds_to_range(range)?.into_inner();
+        // the first range that does not end before `start`
+        let first = self.position(start) / 2;
+        // one past the last range that starts at or before `end`
+        let last = self.position(end).div_ceil(2);
+        (first < last).then(|| Iter {
+            boundaries: &self.boundaries[first * 2..(last * 2).min(self.boundaries.len())],
+        })
+    }
+
+    /// Check if the given range intersects with any ranges inside of the list.
+    pub fn intersects<R: RangeBounds<Idx>>(&self, range: R) -> bool {
+        self.lookup_range(range).is_some()
+    }
+
+    /// Checks whether `self` is a subset of `other`, meaning whether self's ranges all lie somewhere inside of `other`.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.iter().all(|range| other.contains_range(range))
+    }
+
+    /// Checks whether `self` is a superset of `other`.
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Checks whether `self` and `other` are entirely disjoint.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        if self.len() <= other.len() {
+            !self.iter().any(|range| other.intersects(range))
+        } else {
+            !other.iter().any(|range| self.intersects(range))
+        }
+    }
+
+    /// Adds a unit range(index..=index) to the list.
+    ///
+    /// If the unit is not part of an existing range, `true` is returned.
+    ///
+    /// If the unit already exists in a range, `false` is returned.
+    pub fn insert_unit(&mut self, index: Idx) -> bool {
+        let inserted = !self.contains(index);
+        self.insert_range(index..=index);
+        inserted
+    }
+
+    pub fn insert_range<R: RangeBounds<Idx>>(&mut self, range: R) {
+        if let Some(range) = bounds_to_range(range) {
+            self.set_range(range, true);
+        }
+    }
+
+    /// Same as [`Self::insert_unit`], as ranges carry no values both behave the same.
+    pub fn add_unit(&mut self, index: Idx) -> bool {
+        self.insert_unit(index)
+    }
+
+    /// Same as [`Self::insert_range`], as ranges carry no values both behave the same.
+    pub fn add_range<R: RangeBounds<Idx>>(&mut self, range: R) {
+        self.insert_range(range);
+    }
+
+    pub fn remove_range<R: RangeBounds<Idx>>(&mut self, range: R) {
+        if let Some(range) = bounds_to_range(range) {
+            self.set_range(range, false);
+        }
+    }
+
+    /// Inverts all ranges, meaning existing ranges will be removed and parts that were previously
+    /// not covered by ranges will now be covered.
+    pub fn invert(&mut self) {
+        // toggling membership at the very first index toggles it everywhere
+        if self.boundaries.first() == Some(&Idx::min_value()) {
+            self.boundaries.remove(0);
+        } else {
+            self.boundaries.insert(0, Idx::min_value());
+        }
+    }
+
+    /// An iterator over the ranges contained in this list.
+    pub fn iter(&self) -> Iter<'_, Idx> {
+        Iter {
+            boundaries: &self.boundaries,
+        }
+    }
+
+    /// Returns the number of boundaries that are less than or equal to `index`, which is odd if
+    /// and only if `index` is contained in the list.
+    fn position(&self, index: Idx) -> usize {
+        self.boundaries.partition_point(|&b| b <= index)
+    }
+
+    fn range_at(&self, idx: usize) -> Option<RangeInclusive<Idx>> {
+        range_from_chunk(self.boundaries.get(idx * 2..)?)
+    }
+
+    /// Sets the membership of all indices in `range` to `member`.
+    fn set_range(&mut self, range: RangeInclusive<Idx>, member: bool) {
+        let (start, end) = range.into_inner();
+        let end = successor(end);
+        let lo = self.boundaries.partition_point(|&b| b < start);
+        let hi = match end {
+            Some(end) => self.boundaries.partition_point(|&b| b <= end),
+            None => self.boundaries.len(),
+        };
+        // if the membership in front of the range differs we need a boundary at its start, same
+        // goes for the membership past its end
+        let start = ((lo % 2 == 1) != member).then_some(start);
+        let end = end.filter(|_| (hi % 2 == 1) != member);
+        self.boundaries.splice(lo..hi, start.into_iter().chain(end));
+    }
+
+    /// Combines two lists by walking their boundaries in lockstep, `op` is told whether a position
+    /// is part of either list and decides whether it will be part of the result.
+    fn merge(&self, other: &Self, op: impl Fn(bool, bool) -> bool) -> Self {
+        let (a, b) = (&self.boundaries, &other.boundaries);
+        let mut boundaries = Vec::with_capacity(a.len() + b.len());
+        let (mut i, mut j) = (0, 0);
+        let (mut in_a, mut in_b, mut member) = (false, false, op(false, false));
+        if member {
+            boundaries.push(Idx::min_value());
+        }
+        while i < a.len() || j < b.len() {
+            let next = match (a.get(i), b.get(j)) {
+                (Some(&x), Some(&y)) => x.min(y),
+                (Some(&x), None) => x,
+                (None, Some(&y)) => y,
+                (None, None) => unreachable!(),
+            };
+            if a.get(i) == Some(&next) {
+                in_a = !in_a;
+                i += 1;
+            }
+            if b.get(j) == Some(&next) {
+                in_b = !in_b;
+                j += 1;
+            }
+            if op(in_a, in_b) != member {
+                member = !member;
+                if boundaries.last() == Some(&next) {
+                    // only possible for the `min_value()` boundary pushed in front of the loop
+                    boundaries.pop();
+                } else {
+                    boundaries.push(next);
+                }
+            }
+        }
+        BoundaryList { boundaries }
+    }
+}
+
+impl<Idx: OrderedIndex> From<&InversionList<Idx>> for BoundaryList<Idx> {
+    fn from(list: &InversionList<Idx>) -> Self {
+        let mut boundaries: Vec<Idx> = Vec::with_capacity(list.len() * 2);
+        for range in list {
+            let (start, end) = range.into_inner();
+            // adjacent ranges share their boundary, so it cancels out
+            if boundaries.last() == Some(&start) {
+                boundaries.pop();
+            } else {
+                boundaries.push(start);
+            }
+            match successor(end) {
+                Some(end) => boundaries.push(end),
+                None => break,
+            }
+        }
+        BoundaryList { boundaries }
+    }
+}
+
+impl<Idx: OrderedIndex> From<InversionList<Idx>> for BoundaryList<Idx> {
+    fn from(list: InversionList<Idx>) -> Self {
+        BoundaryList::from(&list)
+    }
+}
+
+impl<Idx: OrderedIndex> From<&BoundaryList<Idx>> for InversionList<Idx> {
+    fn from(list: &BoundaryList<Idx>) -> Self {
+        InversionList(InversionMap {
+            ranges: list
+                .iter()
+                .map(|range| Entry {
+                    start: *range.start(),
+                    end: *range.end(),
+                    value: (),
+                })
+                .collect(),
+        })
+    }
+}
+
+impl<Idx: OrderedIndex> From<BoundaryList<Idx>> for InversionList<Idx> {
+    fn from(list: BoundaryList<Idx>) -> Self {
+        InversionList::from(&list)
+    }
+}
+
+impl<Idx: OrderedIndex> FromIterator<Range<Idx>> for BoundaryList<Idx> {
+    fn from_iter<T: IntoIterator<Item = Range<Idx>>>(iter: T) -> Self {
+        let mut res = BoundaryList::new();
+        for range in iter {
+            res.insert_range(range);
+        }
+        res
+    }
+}
+
+impl<Idx: OrderedIndex> FromIterator<RangeInclusive<Idx>> for BoundaryList<Idx> {
+    fn from_iter<T: IntoIterator<Item = RangeInclusive<Idx>>>(iter: T) -> Self {
+        let mut res = BoundaryList::new();
+        for range in iter {
+            res.insert_range(range);
+        }
+        res
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Iter<'a, Idx: OrderedIndex> {
+    boundaries: &'a [Idx],
+}
+
+impl<Idx: OrderedIndex> Iterator for Iter<'_, Idx> {
+    type Item = RangeInclusive<Idx>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let range = range_from_chunk(self.boundaries)?;
+        self.boundaries = self.boundaries.get(2..).unwrap_or_default();
+        Some(range)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.boundaries.len().div_ceil(2);
+        (len, Some(len))
+    }
+}
+
+impl<Idx: OrderedIndex> DoubleEndedIterator for Iter<'_, Idx> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        // a trailing unpaired boundary is a range running up to the maximum index
+        let last = self.boundaries.len().checked_sub(1)? & !1;
+        let range = range_from_chunk(&self.boundaries[last..])?;
+        self.boundaries = &self.boundaries[..last];
+        Some(range)
+    }
+}
+
+impl<Idx: OrderedIndex> FusedIterator for Iter<'_, Idx> {}
+impl<Idx: OrderedIndex> ExactSizeIterator for Iter<'_, Idx> {}
+
+impl<'a, Idx: OrderedIndex> IntoIterator for &'a BoundaryList<Idx> {
+    type Item = RangeInclusive<Idx>;
+    type IntoIter = Iter<'a, Idx>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<Idx: OrderedIndex> ops::Not for BoundaryList<Idx> {
+    type Output = BoundaryList<Idx>;
+    fn not(mut self) -> BoundaryList<Idx> {
+        self.invert();
+        self
+    }
+}
+
+impl<Idx: OrderedIndex> ops::Not for &BoundaryList<Idx> {
+    type Output = BoundaryList<Idx>;
+    fn not(self) -> BoundaryList<Idx> {
+        !self.clone()
+    }
+}
+
+/// Implements a set operator, its owned variants and its assigning variants in terms of
+/// [`BoundaryList::merge`].
+macro_rules! impl_set_op {
+    ($($op:ident::$fn:ident, $op_assign:ident::$fn_assign:ident => $merge:expr;)*) => {
+        $(
+            impl<Idx: OrderedIndex> ops::$op<&BoundaryList<Idx>> for &BoundaryList<Idx> {
+                type Output = BoundaryList<Idx>;
+                fn $fn(self, rhs: &BoundaryList<Idx>) -> Self::Output {
+                    self.merge(rhs, $merge)
+                }
+            }
+
+            impl<Idx: OrderedIndex> ops::$op<&BoundaryList<Idx>> for BoundaryList<Idx> {
+                type Output = BoundaryList<Idx>;
+                fn $fn(self, rhs: &BoundaryList<Idx>) -> Self::Output {
+                    self.merge(rhs, $merge)
+                }
+            }
+
+            impl<Idx: OrderedIndex> ops::$op<BoundaryList<Idx>> for &BoundaryList<Idx> {
+                type Output = BoundaryList<Idx>;
+                fn $fn(self, rhs: BoundaryList<Idx>) -> Self::Output {
+                    self.merge(&rhs, $merge)
+                }
+            }
+
+            impl<Idx: OrderedIndex> ops::$op<BoundaryList<Idx>> for BoundaryList<Idx> {
+                type Output = BoundaryList<Idx>;
+                fn $fn(self, rhs: BoundaryList<Idx>) -> Self::Output {
+                    self.merge(&rhs, $merge)
+                }
+            }
+
+            impl<Idx: OrderedIndex> ops::$op_assign for BoundaryList<Idx> {
+                fn $fn_assign(&mut self, rhs: Self) {
+                    *self = self.merge(&rhs, $merge);
+                }
+            }
+
+            impl<Idx: OrderedIndex> ops::$op_assign<&BoundaryList<Idx>> for BoundaryList<Idx> {
+                fn $fn_assign(&mut self, rhs: &BoundaryList<Idx>) {
+                    *self = self.merge(rhs, $merge);
+                }
+            }
+        )*
+    };
+}
+
+impl_set_op! {
+    BitOr::bitor, BitOrAssign::bitor_assign => |a, b| a || b;
+    BitAnd::bitand, BitAndAssign::bitand_assign => |a, b| a && b;
+    Sub::sub, SubAssign::sub_assign => |a, b| a && !b;
+    BitXor::bitxor, BitXorAssign::bitxor_assign => |a, b| a != b;
+}
@@ -0,0 +1,126 @@
+use super::*;
+use crate::testing::random_list;
+
+macro_rules! bl {
+    ($($range:expr),* $(,)?) => {
+        {
+            #[allow(unused_mut)]
+            let mut bl = BoundaryList::new();
+            $(bl.insert_range($range);)*
+            bl
+        }
+    };
+}
+
+#[test]
+fn insert_range() {
+    let mut bl: BoundaryList<u8> = bl![10..20];
+    assert_eq!(bl.boundaries(), [10, 20]);
+    bl.insert_range(20..25);
+    assert_eq!(bl.boundaries(), [10, 25]);
+    bl.insert_range(5..10);
+    assert_eq!(bl.boundaries(), [5, 25]);
+    bl.insert_range(30..40);
+    bl.insert_range(50..);
+    assert_eq!(bl.boundaries(), [5, 25, 30, 40, 50]);
+    assert_eq!(bl.last(), Some(50..=255));
+    bl.insert_range(24..=45);
+    assert_eq!(bl.boundaries(), [5, 46, 50]);
+    bl.insert_range(..);
+    assert_eq!(bl.boundaries(), [0]);
+    assert_eq!(bl.iter().collect::<Vec<_>>(), [0..=255]);
+}
+
+#[test]
+fn remove_range() {
+    let mut bl: BoundaryList<u8> = bl![..];
+    bl.remove_range(10..20);
+    assert_eq!(bl.boundaries(), [0, 10, 20]);
+    bl.remove_range(200..);
+    assert_eq!(bl.boundaries(), [0, 10, 20, 200]);
+    bl.remove_range(5..=30);
+    assert_eq!(bl.boundaries(), [0, 5, 31, 200]);
+    bl.remove_range(..=5);
+    assert_eq!(bl.boundaries(), [31, 200]);
+    bl.remove_range(31..200);
+    assert!(bl.is_empty());
+}
+
+#[test]
+fn queries() {
+    let bl: BoundaryList<u8> = bl![1..10, 15..26, 61..=255];
+    assert_eq!(bl.len(), 3);
+    assert!(bl.contains(1));
+    assert!(!bl.contains(10));
+    assert!(bl.contains(255));
+    assert_eq!(bl.lookup(20), Some(15..=25));
+    assert_eq!(bl.lookup(12), None);
+    assert_eq!(bl.span(), Some(1..=255));
+    assert!(bl.contains_range(2..5));
+    assert!(!bl.contains_range(5..20));
+    assert!(bl.intersects(5..20));
+    assert!(!bl.intersects(10..15));
+    assert_eq!(
+        bl.lookup_range(5..=15).unwrap().collect::<Vec<_>>(),
+        [1..=9, 15..=25]
+    );
+    assert_eq!(
+        bl.lookup_range(100..).unwrap().collect::<Vec<_>>(),
+        [61..=255]
+    );
+    assert!(bl.lookup_range(26..61).is_none());
+    assert!(bl.is_subset(&bl![..]));
+    assert!(bl.is_disjoint(&bl![10..15, 26..61]));
+}
+
+#[test]
+fn iter_rev() {
+    let bl: BoundaryList<u8> = bl![1..10, 15..26, 61..=255];
+    assert!(bl.iter().rev().eq([61..=255, 15..=25, 1..=9]));
+    let mut iter = bl.lookup_range(20..).unwrap();
+    assert_eq!(iter.next_back(), Some(61..=255));
+    assert_eq!(iter.next(), Some(15..=25));
+    assert_eq!(iter.next_back(), None);
+    let bl: BoundaryList<u8> = bl![1..10, 15..26];
+    assert!(bl.iter().rev().eq([15..=25, 1..=9]));
+    assert!(BoundaryList::<u8>::new().iter().next_back().is_none());
+}
+
+#[test]
+fn invert() {
+    let mut bl: BoundaryList<u8> = bl![1..10, 20..=255];
+    bl.invert();
+    assert_eq!(bl, bl![0..1, 10..20]);
+    assert_eq!(!bl, bl![1..10, 20..=255]);
+}
+
+#[test]
+fn conversions() {
+    let mut il: InversionList<u8> = InversionList::new();
+    il.insert_range(0..5);
+    il.insert_range(5..10);
+    il.insert_range(20..=255);
+    let bl = BoundaryList::from(&il);
+    assert_eq!(bl.boundaries(), [0, 10, 20]);
+    assert_eq!(InversionList::from(bl), il);
+}
+
+#[test]
+fn set_ops_match_inversion_list() {
+    let mut seed = 0x9e37_79b9;
+    for _ in 0..300 {
+        let (a, _) = random_list(&mut seed);
+        let (b, _) = random_list(&mut seed);
+        let (ba, bb) = (BoundaryList::from(&a), BoundaryList::from(&b));
+        assert_eq!(InversionList::from(&ba | &bb), &a | &b);
+        assert_eq!(InversionList::from(&ba & &bb), &a & &b);
+        assert_eq!(InversionList::from(&ba - &bb), &a - &b);
+        assert_eq!(InversionList::from(&ba ^ &bb), &a ^ &b);
+
+        let mut c = ba.clone();
+        for range in &bb {
+            c.remove_range(range);
+        }
+        assert_eq!(c, &ba - &bb);
+    }
+}
@@ -0,0 +1,468 @@
+//! An inversion map backed by a B-tree, trading slower lookups for `O(log n)` mutations.
+
+use core::iter::{FromIterator, FusedIterator};
+use core::ops::{RangeBounds, RangeInclusive};
+
+use alloc::collections::btree_map::{self, BTreeMap};
+
+use crate::map::Entry;
+use crate::util::{bounds_to_range, predecessor, successor};
+use crate::{InversionMap, OrderedIndex};
+
+#[cfg(test)]
+mod test;
+
+/// An inversion map that keeps its ranges in a [`BTreeMap`] keyed by their start.
+///
+/// Unlike [`InversionMap`], which has to shift its backing vector on every edit, inserting,
+/// adding, removing and splitting ranges here costs `O(log n)` per touched entry.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct BTreeInversionMap<Idx, V> {
+    // start => (inclusive end, value)
+    tree: BTreeMap<Idx, (Idx, V)>,
+}
+
+/// The entries of a [`BTreeInversionMap`] overlapping a range.
+pub struct EntriesRef<'im, Idx, V> {
+    range: btree_map::Range<'im, Idx, (Idx, V)>,
+}
+
+impl<'im, Idx: OrderedIndex, V> EntriesRef<'im, Idx, V> {
+    pub fn is_empty(&self) -> bool {
+        self.range.clone().next().is_none()
+    }
+
+    /// Returns the number of entries, this walks all of them.
+    pub fn len(&self) -> usize {
+        self.range.clone().count()
+    }
+
+    pub fn iter(&self) -> Iter<'im, Idx, V> {
+        Iter {
+            iter: self.range.clone(),
+        }
+    }
+}
+
+impl<Idx, V> BTreeInversionMap<Idx, V> {
+    pub const fn new() -> Self {
+        BTreeInversionMap {
+            tree: BTreeMap::new(),
+        }
+    }
+}
+
+// region: delegate methods
+impl<Idx: OrderedIndex, V> BTreeInversionMap<Idx, V> {
+    #[inline]
+    pub fn clear(&mut self) {
+        self.tree.clear();
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    #[inline]
+    pub fn start(&self) -> Option<Idx> {
+        self.tree.first_key_value().map(|(&start, _)| start)
+    }
+
+    /// Returns the last index covered by the map, this is an inclusive bound.
+    #[inline]
+    pub fn end(&self) -> Option<Idx> {
+        self.tree.last_key_value().map(|(_, &(end, _))| end)
+    }
+
+    #[inline]
+    pub fn first(&self) -> Option<(RangeInclusive<Idx>, &V)> {
+        self.tree
+            .first_key_value()
+            .map(|(&start, (end, value))| (start..=*end, value))
+    }
+
+    #[inline]
+    pub fn last(&self) -> Option<(RangeInclusive<Idx>, &V)> {
+        self.tree
+            .last_key_value()
+            .map(|(&start, (end, value))| (start..=*end, value))
+    }
+}
+// endregion
+
+impl<Idx: OrderedIndex, V> BTreeInversionMap<Idx, V> {
+    /// Checks whether the given index is contained in the map.
+    pub fn contains(&self, index: Idx) -> bool {
+        self.lookup(index).is_some()
+    }
+
+    /// Checks whether a single range of the map fully contains the given range.
+    ///
+    /// Empty ranges are never contained.
+    pub fn contains_range<R: RangeBounds<Idx>>(&self, range: R) -> bool {
+        bounds_to_range(range).is_some_and(|range| {
+            self.lookup(*range.start())
+                .is_some_and(|(it, _)| it.end() >= range.end())
+        })
+    }
+
+    /// Looks up the range and value associated with the given index.
+    pub fn lookup(&self, index: Idx) -> Option<(RangeInclusive<Idx>, &V)> {
+        self.tree
+            .range(..=index)
+            .next_back()
+            .filter(|(_, (end, _))| index <= *end)
+            .map(|(&start, (end, value))| (start..=*end, value))
+    }
+
+    /// Looks up all entries whose ranges overlap with the given range.
+    pub fn lookup_range<R: RangeBounds<Idx>>(&self, range: R) -> Option<EntriesRef<'_, Idx, V>> {
+        let entries = self.overlapping(bounds_to_range(range)?);
+        (!entries.is_empty()).then_some(entries)
+    }
+
+    /// Check if the given range intersects with any ranges inside of the map.
+    pub fn intersects<R: RangeBounds<Idx>>(&self, range: R) -> bool {
+        self.lookup_range(range).is_some()
+    }
+
+    /// Returns the complete surrounding range, if any.
+    pub fn span(&self) -> Option<RangeInclusive<Idx>> {
+        Some(self.start()?..=self.end()?)
+    }
+
+    /// An iterator over the ranges and values contained in this map.
+    pub fn iter(&self) -> Iter<'_, Idx, V> {
+        Iter {
+            iter: self.tree.range(..),
+        }
+    }
+
+    fn overlapping(&self, range: RangeInclusive<Idx>) -> EntriesRef<'_, Idx, V> {
+        let (start, end) = range.into_inner();
+        // only the range starting in front of `start` might reach into it
+        let first = match self.tree.range(..start).next_back() {
+            Some((&first, &(first_end, _))) if start <= first_end => first,
+            _ => start,
+        };
+        EntriesRef {
+            range: self.tree.range(first..=end),
+        }
+    }
+
+    /// Removes all entries starting inside of `start..=end`, returning the last one removed.
+    fn remove_starting_within(&mut self, start: Idx, end: Idx) -> Option<Entry<Idx, V>> {
+        let mut last = None;
+        while let Some(key) = self.tree.range(start..=end).next().map(|(&key, _)| key) {
+            last = self
+                .tree
+                .remove_entry(&key)
+                .map(|(start, (end, value))| Entry { start, end, value });
+        }
+        last
+    }
+}
+
+impl<Idx: OrderedIndex, V: Clone> BTreeInversionMap<Idx, V> {
+    /// Inserts a unit range(index..=index) into the map, overwriting the value at `index`.
+    ///
+    /// If the unit is not part of an existing range, `true` is returned.
+    pub fn insert_unit(&mut self, index: Idx, value: V) -> bool {
+        let inserted = !self.contains(index);
+        self.insert_range_with(index..=index, |_| value);
+        inserted
+    }
+
+    /// Inserts a new range with a given value into the map overwriting any ranges that are contained within.
+    /// Ranges that partially overlap will be shortened or split accordingly.
+    pub fn insert_range<R: RangeBounds<Idx>>(&mut self, range: R, value: V) {
+        self.insert_range_with(range, |_| value);
+    }
+
+    /// Inserts a new range with a value produced by `value` into the map. `value` gets passed all
+    /// overlapping entries. If start or end overlap with a range, the overlapping range will be
+    /// split accordingly.
+    pub fn insert_range_with<R: RangeBounds<Idx>>(
+        &mut self,
+        range: R,
+        value: impl FnOnce(EntriesRef<'_, Idx, V>) -> V,
+    ) {
+        let Some(range) = bounds_to_range(range) else {
+            return;
+        };
+        let value = value(self.overlapping(range.clone()));
+        let (start, end) = range.into_inner();
+
+        let mut right = None;
+        if let Some((_, (left_end, left_value))) = self.tree.range_mut(..start).next_back() {
+            if start <= *left_end {
+                if end < *left_end {
+                    // the new range lies strictly inside of this one, so it gets split in two
+                    right = Some((*left_end, left_value.clone()));
+                }
+                // `left_start < start` so this can't underflow
+                *left_end = predecessor(start).unwrap();
+            }
+        }
+        if let Some(last) = self.remove_starting_within(start, end) {
+            if end < last.end {
+                right = Some((last.end, last.value));
+            }
+        }
+        if let Some(right) = right {
+            // `end < right.0` so this can't overflow
+            self.tree.insert(successor(end).unwrap(), right);
+        }
+        self.tree.insert(start, (end, value));
+    }
+
+    /// Removes the range of values overlapping the given range.
+    pub fn remove_range<R: RangeBounds<Idx>>(
+        &mut self,
+        range: R,
+        split_boundary_left: impl FnOnce(RangeInclusive<Idx>, &V) -> V,
+        split_boundary_right: impl FnOnce(RangeInclusive<Idx>, &V) -> V,
+    ) {
+        let Some(range) = bounds_to_range(range) else {
+            return;
+        };
+        let (start, end) = range.into_inner();
+
+        if let Some((&left_start, (left_end, left_value))) =
+            self.tree.range_mut(..start).next_back()
+        {
+            if start <= *left_end {
+                let left_range = left_start..=*left_end;
+                if end < *left_end {
+                    // no other entry can overlap, this one gets split on both sides
+                    let right = (
+                        *left_end,
+                        split_boundary_right(left_range.clone(), left_value),
+                    );
+                    *left_value = split_boundary_left(left_range, left_value);
+                    *left_end = predecessor(start).unwrap();
+                    // `end < right.0` so this can't overflow
+                    self.tree.insert(successor(end).unwrap(), right);
+                    return;
+                }
+                *left_value = split_boundary_left(left_range, left_value);
+                // `left_start < start` so this can't underflow
+                *left_end = predecessor(start).unwrap();
+            }
+        }
+        if let Some(last) = self.remove_starting_within(start, end) {
+            if end < last.end {
+                let value = split_boundary_right(last.range(), &last.value);
+                // `end < last.end` so this can't overflow
+                self.tree.insert(successor(end).unwrap(), (last.end, value));
+            }
+        }
+    }
+
+    /// Splits the range that contains `at` in two with `at` being the split point.
+    ///
+    /// Returns `true` if a range contains `at`, in which case a range now starts at `at`.
+    /// If `at` is equal to the start of the range it is in, no split occurs.
+    ///
+    /// Split ranges that are right next to each other will not be recognized as one, see
+    /// [`InversionMap::split`].
+    pub fn split(&mut self, at: Idx) -> bool {
+        self.split_with(at, |_, v| (v.clone(), v))
+    }
+
+    /// Like [`split`](Self::split) but allows for the split to be done with a custom function.
+    pub fn split_with(
+        &mut self,
+        at: Idx,
+        splitter: impl FnOnce(RangeInclusive<Idx>, V) -> (V, V),
+    ) -> bool {
+        let Some((&start, (end, value))) = self.tree.range_mut(..=at).next_back() else {
+            return false;
+        };
+        if *end < at {
+            return false;
+        }
+        if start == at {
+            return true;
+        }
+        let right_end = *end;
+        // `start < at` so this can't underflow
+        *end = predecessor(at).unwrap();
+        // FIXME: The clone should not be necessary here
+        let (left, right) = splitter(start..=*end, value.clone());
+        *value = left;
+        self.tree.insert(at, (right_end, right));
+        true
+    }
+}
+
+impl<Idx: OrderedIndex, V> BTreeInversionMap<Idx, V> {
+    /// Adds a unit range(index..=index) to the map.
+    ///
+    /// If the unit is not part of an existing range, `true` is returned.
+    ///
+    /// If the unit already exists in a range, `false` is returned and the range value will be set
+    /// to `value`.
+    pub fn add_unit(&mut self, index: Idx, value: V) -> bool {
+        match self.tree.range_mut(..=index).next_back() {
+            Some((_, (end, old))) if index <= *end => {
+                *old = value;
+                false
+            }
+            _ => {
+                self.tree.insert(index, (index, value));
+                true
+            }
+        }
+    }
+
+    pub fn add_range<R: RangeBounds<Idx>>(&mut self, range: R, value: V) {
+        self.add_range_with(range, |_| value);
+    }
+
+    /// Adds a new range with a value produced by `value` into the map. `value` gets passed all
+    /// overlapping entries. If start or end overlap with a range, the overlapping range will be
+    /// extended accordingly.
+    pub fn add_range_with<R: RangeBounds<Idx>>(
+        &mut self,
+        range: R,
+        value: impl FnOnce(EntriesRef<'_, Idx, V>) -> V,
+    ) {
+        let Some(range) = bounds_to_range(range) else {
+            return;
+        };
+        let value = value(self.overlapping(range.clone()));
+        let (mut start, mut end) = range.into_inner();
+
+        if let Some((&left_start, &(left_end, _))) = self.tree.range(..start).next_back() {
+            if start <= left_end {
+                self.tree.remove(&left_start);
+                start = left_start;
+                end = end.max(left_end);
+            }
+        }
+        if let Some(last) = self.remove_starting_within(start, end) {
+            end = end.max(last.end);
+        }
+        self.tree.insert(start, (end, value));
+    }
+}
+
+impl<Idx: OrderedIndex, V> From<InversionMap<Idx, V>> for BTreeInversionMap<Idx, V> {
+    fn from(map: InversionMap<Idx, V>) -> Self {
+        BTreeInversionMap {
+            tree: map
+                .ranges
+                .into_entries()
+                .into_iter()
+                .map(|Entry { start, end, value }| (start, (end, value)))
+                .collect(),
+        }
+    }
+}
+
+impl<Idx: OrderedIndex, V> From<BTreeInversionMap<Idx, V>> for InversionMap<Idx, V> {
+    fn from(map: BTreeInversionMap<Idx, V>) -> Self {
+        InversionMap {
+            ranges: map
+                .tree
+                .into_iter()
+                .map(|(start, (end, value))| Entry { start, end, value })
+                .collect(),
+        }
+    }
+}
+
+impl<Idx: OrderedIndex, V: Clone> FromIterator<(RangeInclusive<Idx>, V)>
+    for BTreeInversionMap<Idx, V>
+{
+    fn from_iter<T: IntoIterator<Item = (RangeInclusive<Idx>, V)>>(iter: T) -> Self {
+        let mut res = Self::new();
+        iter.into_iter()
+            .for_each(|(range, value)| res.insert_range(range, value));
+        res
+    }
+}
+
+impl<'im, Idx: OrderedIndex, V> IntoIterator for &'im BTreeInversionMap<Idx, V> {
+    type Item = (RangeInclusive<Idx>, &'im V);
+    type IntoIter = Iter<'im, Idx, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<Idx: OrderedIndex, V> IntoIterator for BTreeInversionMap<Idx, V> {
+    type Item = (RangeInclusive<Idx>, V);
+    type IntoIter = IntoIter<Idx, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            iter: self.tree.into_iter(),
+        }
+    }
+}
+
+pub struct Iter<'im, Idx, V> {
+    iter: btree_map::Range<'im, Idx, (Idx, V)>,
+}
+
+impl<'im, Idx: OrderedIndex, V> Iterator for Iter<'im, Idx, V> {
+    type Item = (RangeInclusive<Idx>, &'im V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|(&start, (end, value))| (start..=*end, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Idx: OrderedIndex, V> DoubleEndedIterator for Iter<'_, Idx, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next_back()
+            .map(|(&start, (end, value))| (start..=*end, value))
+    }
+}
+
+impl<Idx: OrderedIndex, V> FusedIterator for Iter<'_, Idx, V> {}
+
+pub struct IntoIter<Idx, V> {
+    iter: btree_map::IntoIter<Idx, (Idx, V)>,
+}
+
+impl<Idx: OrderedIndex, V> Iterator for IntoIter<Idx, V> {
+    type Item = (RangeInclusive<Idx>, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|(start, (end, value))| (start..=end, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Idx: OrderedIndex, V> DoubleEndedIterator for IntoIter<Idx, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next_back()
+            .map(|(start, (end, value))| (start..=end, value))
+    }
+}
+
+impl<Idx: OrderedIndex, V> ExactSizeIterator for IntoIter<Idx, V> {}
+impl<Idx: OrderedIndex, V> FusedIterator for IntoIter<Idx, V> {}
@@ -0,0 +1,135 @@
+use super::*;
+use crate::testing::{random_range, xorshift};
+
+use alloc::vec::Vec;
+
+fn to_vec<Idx: OrderedIndex, V: Clone>(
+    iter: impl Iterator<Item = (RangeInclusive<Idx>, V)>,
+) -> Vec<(RangeInclusive<Idx>, V)> {
+    iter.collect()
+}
+
+#[test]
+fn insert_range_splits() {
+    let mut bt = BTreeInversionMap::new();
+    bt.insert_range(0..10, 0);
+    bt.insert_range(3..5, 1);
+    assert_eq!(
+        to_vec(bt.iter().map(|(r, &v)| (r, v))),
+        [(0..=2, 0), (3..=4, 1), (5..=9, 0)]
+    );
+    bt.insert_range(2..=6, 2);
+    assert_eq!(
+        to_vec(bt.iter().map(|(r, &v)| (r, v))),
+        [(0..=1, 0), (2..=6, 2), (7..=9, 0)]
+    );
+}
+
+#[test]
+fn add_range_merges() {
+    let mut bt = BTreeInversionMap::new();
+    bt.add_range(0..5, 0);
+    bt.add_range(10..15, 1);
+    bt.add_range(20..25, 2);
+    bt.add_range_with(3..=20, |entries| entries.iter().map(|(_, &v)| v).sum());
+    assert_eq!(to_vec(bt.into_iter()), [(0..=24, 3)]);
+}
+
+#[test]
+fn lookup_range() {
+    let mut bt = BTreeInversionMap::new();
+    bt.insert_range(0..5, 0);
+    bt.insert_range(10..15, 1);
+    bt.insert_range(20..25, 2);
+    assert!(bt.lookup_range(5..10).is_none());
+    let entries = bt.lookup_range(4..=20).unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(
+        to_vec(entries.iter().map(|(r, &v)| (r, v))),
+        [(0..=4, 0), (10..=14, 1), (20..=24, 2)]
+    );
+    assert_eq!(bt.lookup(12), Some((10..=14, &1)));
+    assert_eq!(bt.lookup(15), None);
+    assert!(bt.contains_range(10..15));
+    assert!(!bt.contains_range(10..16));
+}
+
+#[test]
+fn iter_rev() {
+    let mut bt = BTreeInversionMap::new();
+    bt.insert_range(0..5, 0);
+    bt.insert_range(10..15, 1);
+    bt.insert_range(20..25, 2);
+    assert_eq!(
+        to_vec(bt.iter().rev().map(|(r, &v)| (r, v))),
+        [(20..=24, 2), (10..=14, 1), (0..=4, 0)]
+    );
+    let mut iter = bt.into_iter();
+    assert_eq!(iter.next_back(), Some((20..=24, 2)));
+    assert_eq!(iter.next(), Some((0..=4, 0)));
+    assert_eq!(to_vec(iter.rev()), [(10..=14, 1)]);
+}
+
+#[test]
+fn split_max_value() {
+    let mut bt = BTreeInversionMap::new();
+    bt.insert_range(0..=u8::MAX, 0);
+    assert!(bt.split(u8::MAX));
+    assert!(bt.split(0));
+    assert_eq!(to_vec(bt.into_iter()), [(0..=254, 0), (255..=255, 0)]);
+}
+
+// Runs random edits against both backends and checks that they agree after every step.
+#[test]
+fn matches_inversion_map() {
+    let mut seed = 0x2545_f491;
+    let mut bt = BTreeInversionMap::<u8, u32>::new();
+    let mut im = InversionMap::<u8, u32>::new();
+    for step in 0..2000 {
+        let (start, end) = random_range(&mut seed, 40).into_inner();
+        match xorshift(&mut seed) % 6 {
+            0 => {
+                bt.insert_range(start..=end, step);
+                im.insert_range(start..=end, step);
+            }
+            1 => {
+                let sum = |len: usize| step + len as u32;
+                bt.insert_range_with(start..=end, |e| sum(e.len()));
+                im.insert_range_with(start..=end, |e| sum(e.len()));
+            }
+            2 => {
+                let sum = |it: &mut dyn Iterator<Item = u32>| it.fold(step, u32::wrapping_add);
+                bt.add_range_with(start..=end, |e| sum(&mut e.iter().map(|(_, &v)| v)));
+                im.add_range_with(start..=end, |e| sum(&mut e.iter().map(|(_, &v)| v)));
+            }
+            3 => {
+                let left = |r: RangeInclusive<u8>, &v: &u32| v ^ *r.start() as u32;
+                let right = |r: RangeInclusive<u8>, &v: &u32| v ^ ((*r.end() as u32) << 8);
+                bt.remove_range(start..=end, left, right);
+                im.remove_range(start..=end, left, right);
+            }
+            4 => {
+                let splitter = |r: RangeInclusive<u8>, v: u32| (v, v ^ *r.end() as u32);
+                assert_eq!(
+                    bt.split_with(start, splitter),
+                    im.split_with(start, splitter).is_some()
+                );
+            }
+            _ => {
+                assert_eq!(bt.add_unit(start, step), im.add_unit(start, step));
+                assert_eq!(bt.insert_unit(end, step), im.insert_unit(end, step));
+            }
+        }
+        assert!(bt
+            .iter()
+            .map(|(r, &v)| (r, v))
+            .eq(im.iter().map(|(r, &v)| (r, v))));
+        assert_eq!(bt.lookup(end), im.lookup(end));
+        assert_eq!(
+            bt.lookup_range(start..=end).map_or(0, |e| e.len()),
+            im.lookup_range(start..=end).map_or(0, |e| e.len())
+        );
+    }
+    assert_eq!(InversionMap::from(bt.clone()), im);
+    assert_eq!(BTreeInversionMap::from(im), bt);
+}
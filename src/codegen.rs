@@ -0,0 +1,160 @@
+//! Generation of Rust source code declaring static tables, meant to be used from build scripts.
+//!
+//! Lists are emitted as [`StaticInversionList`](crate::StaticInversionList)s built from their
+//! toggle points, so the generated code depends on this crate. Maps are emitted as slices of tuples
+//! holding the inclusive start and end of each range along with its value, so that ranges touching
+//! [`OrderedIndex::max_value()`] can be represented. Indices are printed with their
+//! [`Debug`](core::fmt::Debug) implementation, which yields valid literals for the primitive
+//! integers and `char`.
+
+use core::fmt::{self, Write};
+
+use alloc::string::String;
+
+use crate::util::successor;
+use crate::{InversionList, InversionMap, OrderedIndex};
+
+#[cfg(test)]
+mod test;
+
+/// An index type that generated code can name.
+pub trait CodegenIndex: OrderedIndex {
+    /// The path of the type as written in the generated code.
+    const TYPE_NAME: &'static str;
+}
+
+macro_rules! impl_codegen_index {
+    ($($ty:ident)*) => {
+        $(
+            impl CodegenIndex for $ty {
+                const TYPE_NAME: &'static str = stringify!($ty);
+            }
+        )*
+    };
+}
+impl_codegen_index! { u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize char }
+
+/// Writes a `static` named `name` holding `list` as a
+/// [`StaticInversionList`](crate::StaticInversionList) built from its toggle points.
+///
+/// If `lookup_fn` is given, a function of that name is emitted as well, taking an index and
+/// returning whether it is contained in the list.
+pub fn write_list<Idx: CodegenIndex, W: Write>(
+    out: &mut W,
+    name: &str,
+    list: &InversionList<Idx>,
+    lookup_fn: Option<&str>,
+) -> fmt::Result {
+    let idx = Idx::TYPE_NAME;
+    writeln!(
+        out,
+        "pub static {name}: ::inversion_list::StaticInversionList<{idx}> ="
+    )?;
+    writeln!(
+        out,
+        "    ::inversion_list::StaticInversionList::<{idx}>::from_boundaries(&["
+    )?;
+    for range in list {
+        writeln!(out, "        {:?},", range.start())?;
+        // a range reaching the maximum is left open by omitting its end boundary
+        if let Some(end) = successor(*range.end()) {
+            writeln!(out, "        {end:?},")?;
+        }
+    }
+    writeln!(out, "    ]);")?;
+    if let Some(lookup_fn) = lookup_fn {
+        writeln!(out)?;
+        writeln!(out, "pub fn {lookup_fn}(index: {idx}) -> bool {{")?;
+        writeln!(out, "    {name}.contains(index)")?;
+        writeln!(out, "}}")?;
+    }
+    Ok(())
+}
+
+/// Writes a `static` named `name` holding the ranges and values of `map` as
+/// `&[(Idx, Idx, value_ty)]`, with every value written by `write_value`.
+///
+/// `value_ty` should leave its lifetimes elided, e.g. `&str`, as they are `'static` in the table
+/// anyway.
+///
+/// If `lookup_fn` is given, a function of that name is emitted as well, taking an index and
+/// returning its value if it is contained in the table. The function returns an
+/// `Option<&'static T>`, where `T` is the value type with one level of reference removed if it is
+/// a reference, so a table of `&str` is looked up as `Option<&'static str>`.
+pub fn write_map<Idx: CodegenIndex, V, W: Write>(
+    out: &mut W,
+    name: &str,
+    map: &InversionMap<Idx, V>,
+    value_ty: &str,
+    mut write_value: impl FnMut(&mut W, &V) -> fmt::Result,
+    lookup_fn: Option<&str>,
+) -> fmt::Result {
+    let idx = Idx::TYPE_NAME;
+    writeln!(out, "pub static {name}: &[({idx}, {idx}, {value_ty})] = &[")?;
+    for (range, value) in map {
+        write!(out, "    ({:?}, {:?}, ", range.start(), range.end())?;
+        write_value(out, value)?;
+        writeln!(out, "),")?;
+    }
+    writeln!(out, "];")?;
+    if let Some(lookup_fn) = lookup_fn {
+        // references are handed out as they are, everything else gets borrowed from the table
+        let (referent, borrow) = match referent(value_ty) {
+            Some(referent) => (referent, ""),
+            None => (value_ty, "&"),
+        };
+        let referent = with_static_lifetimes(referent);
+        writeln!(out)?;
+        writeln!(
+            out,
+            "pub fn {lookup_fn}(index: {idx}) -> Option<&'static {referent}> {{"
+        )?;
+        writeln!(out, "    {name}")?;
+        write_search(out)?;
+        writeln!(out, "        .ok()")?;
+        writeln!(out, "        .map(|i| {borrow}{name}[i].2)")?;
+        writeln!(out, "}}")?;
+    }
+    Ok(())
+}
+
+/// Returns `T` if `ty` is a reference `&T`, skipping an explicit lifetime.
+fn referent(ty: &str) -> Option<&str> {
+    let rest = ty.trim_start().strip_prefix('&')?.trim_start();
+    Some(match rest.strip_prefix('\'') {
+        Some(lifetime) => lifetime
+            .trim_start_matches(|c: char| c.is_alphanumeric() || c == '_')
+            .trim_start(),
+        None => rest,
+    })
+}
+
+/// Spells out the elided lifetimes of the references in `ty` as `'static`, which function
+/// signatures without any borrowed arguments need.
+fn with_static_lifetimes(ty: &str) -> String {
+    let mut out = String::with_capacity(ty.len());
+    let mut rest = ty;
+    while let Some(pos) = rest.find('&') {
+        out.push_str(&rest[..=pos]);
+        rest = &rest[pos + 1..];
+        if !rest.trim_start().starts_with('\'') {
+            out.push_str("'static ");
+            rest = rest.trim_start();
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// The binary search shared by the lookup functions, finding the entry containing `index`.
+fn write_search<W: Write>(out: &mut W) -> fmt::Result {
+    writeln!(out, "        .binary_search_by(|entry| {{")?;
+    writeln!(out, "            if entry.1 < index {{")?;
+    writeln!(out, "                ::core::cmp::Ordering::Less")?;
+    writeln!(out, "            }} else if index < entry.0 {{")?;
+    writeln!(out, "                ::core::cmp::Ordering::Greater")?;
+    writeln!(out, "            }} else {{")?;
+    writeln!(out, "                ::core::cmp::Ordering::Equal")?;
+    writeln!(out, "            }}")?;
+    writeln!(out, "        }})")
+}
@@ -0,0 +1,40 @@
+pub static TABLE: ::inversion_list::StaticInversionList<u8> =
+    ::inversion_list::StaticInversionList::<u8>::from_boundaries(&[
+        0,
+        10,
+        200,
+    ]);
+
+pub fn in_table(index: u8) -> bool {
+    TABLE.contains(index)
+}
+
+pub static CHARS: ::inversion_list::StaticInversionList<char> =
+    ::inversion_list::StaticInversionList::<char>::from_boundaries(&[
+        'a',
+        'c',
+        '\u{80}',
+        '\u{81}',
+        '\u{d7ff}',
+        '\u{e001}',
+    ]);
+
+pub static NAMES: &[(u32, u32, &str)] = &[
+    (0, 4, "low"),
+    (10, 19, "high"),
+];
+
+pub fn name_of(index: u32) -> Option<&'static str> {
+    NAMES
+        .binary_search_by(|entry| {
+            if entry.1 < index {
+                ::core::cmp::Ordering::Less
+            } else if index < entry.0 {
+                ::core::cmp::Ordering::Greater
+            } else {
+                ::core::cmp::Ordering::Equal
+            }
+        })
+        .ok()
+        .map(|i| NAMES[i].2)
+}
@@ -0,0 +1,86 @@
+use super::*;
+
+use alloc::string::String;
+
+fn list() -> InversionList<u8> {
+    let mut il = InversionList::new();
+    il.insert_range(0..10);
+    il.insert_range(200..);
+    il
+}
+
+fn map() -> InversionMap<u32, &'static str> {
+    let mut im = InversionMap::new();
+    im.insert_range(0..=4, "low");
+    im.insert_range(10..20, "high");
+    im
+}
+
+/// The source emitted for [`list`], [`map`] and a small `char` list, checked in as `fixture.rs`.
+fn generate() -> String {
+    let mut out = String::new();
+    write_list(&mut out, "TABLE", &list(), Some("in_table")).unwrap();
+    writeln!(out).unwrap();
+    let chars = InversionList::from_chars("ab\u{80}\u{D7FF}\u{E000}");
+    write_list(&mut out, "CHARS", &chars, None).unwrap();
+    writeln!(out).unwrap();
+    write_map(
+        &mut out,
+        "NAMES",
+        &map(),
+        "&str",
+        |out, value| write!(out, "{value:?}"),
+        Some("name_of"),
+    )
+    .unwrap();
+    out
+}
+
+// The fixture is compiled right here, so a change to the generator that breaks the emitted code
+// fails to build rather than slipping through a comparison of strings.
+mod fixture {
+    include!("fixture.rs");
+}
+
+#[test]
+fn emitted_source() {
+    assert_eq!(generate(), include_str!("fixture.rs"));
+}
+
+#[test]
+fn emitted_list_matches() {
+    let il = list();
+    assert!(fixture::TABLE.iter().eq(il.iter()));
+    for i in 0..=255 {
+        assert_eq!(fixture::in_table(i), il.contains(i));
+    }
+    let chars = InversionList::from_chars("ab\u{80}\u{D7FF}\u{E000}");
+    assert!(fixture::CHARS.iter().eq(chars.iter()));
+}
+
+#[test]
+fn emitted_map_matches() {
+    let im = map();
+    for i in 0..25 {
+        assert_eq!(fixture::name_of(i), im.lookup(i).map(|(_, v)| *v));
+    }
+}
+
+#[test]
+fn value_types() {
+    assert_eq!(referent("&str"), Some("str"));
+    assert_eq!(referent("&'static [u8]"), Some("[u8]"));
+    assert_eq!(referent("(u8, &str)"), None);
+    assert_eq!(with_static_lifetimes("str"), "str");
+    assert_eq!(
+        with_static_lifetimes("(&str, &'static [u8], Option<& u8>)"),
+        "(&'static str, &'static [u8], Option<&'static u8>)"
+    );
+}
+
+// Regenerates `fixture.rs` after an intended change to the emitted source, run with `--ignored`.
+#[test]
+#[ignore]
+fn write_fixture() {
+    std::fs::write("src/codegen/fixture.rs", generate()).unwrap();
+}
@@ -0,0 +1,442 @@
+//! A compact binary encoding for inversion lists and maps.
+//!
+//! An encoding starts with a tag byte telling lists and maps apart, the [`IndexKind`] and the byte
+//! width of the index type, followed by the number of ranges. Each range is then stored as two LEB128
+//! varints, the gap between its start and the end of the previous range and the distance from
+//! its start to its (inclusive) end, followed by the value for maps. Values are written by a
+//! user supplied [`ValueCodec`].
+//!
+//! Decoding validates the whole input without allocating and hands out a view that decodes the
+//! ranges lazily.
+
+use core::fmt;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem::size_of;
+use core::ops::RangeInclusive;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "alloc")]
+use crate::map::Entry;
+use crate::{char_to_index, index_to_char, OrderedIndex};
+#[cfg(feature = "alloc")]
+use crate::{InversionList, InversionMap};
+
+#[cfg(test)]
+mod test;
+
+const LIST_TAG: u8 = b'L';
+const MAP_TAG: u8 = b'M';
+
+/// The kind of an index type, recorded in the encoding so that indices are not reinterpreted as
+/// a type of the same width but with different ordinals, like `char` as `u32`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum IndexKind {
+    Unsigned = b'u',
+    Signed = b'i',
+    Char = b'c',
+}
+
+/// An index that can be mapped onto a gapless range of unsigned integers starting at zero, with
+/// [`OrderedIndex::min_value()`] mapping to zero.
+pub trait VarintIndex: OrderedIndex {
+    const KIND: IndexKind;
+    fn to_ordinal(self) -> u128;
+    /// Returns `None` if the ordinal lies past the one of [`OrderedIndex::max_value()`].
+    fn from_ordinal(ordinal: u128) -> Option<Self>;
+}
+
+macro_rules! impl_unsigned {
+    ($($ty:ty)*) => {
+        $(
+            impl VarintIndex for $ty {
+                const KIND: IndexKind = IndexKind::Unsigned;
+                fn to_ordinal(self) -> u128 {
+                    self as u128
+                }
+                fn from_ordinal(ordinal: u128) -> Option<Self> {
+                    Self::try_from(ordinal).ok()
+                }
+            }
+        )*
+    };
+}
+impl_unsigned! { u8 u16 u32 u64 u128 usize }
+
+macro_rules! impl_signed {
+    ($($ty:ty => $unsigned:ty)*) => {
+        $(
+            impl VarintIndex for $ty {
+                const KIND: IndexKind = IndexKind::Signed;
+                fn to_ordinal(self) -> u128 {
+                    self.wrapping_sub(Self::MIN) as $unsigned as u128
+                }
+                fn from_ordinal(ordinal: u128) -> Option<Self> {
+                    Some((<$unsigned>::try_from(ordinal).ok()? as Self).wrapping_add(Self::MIN))
+                }
+            }
+        )*
+    };
+}
+impl_signed! { i8 => u8 i16 => u16 i32 => u32 i64 => u64 i128 => u128 isize => usize }
+
+impl VarintIndex for char {
+    const KIND: IndexKind = IndexKind::Char;
+    fn to_ordinal(self) -> u128 {
+        char_to_index(self) as u128
+    }
+    fn from_ordinal(ordinal: u128) -> Option<Self> {
+        index_to_char(u32::try_from(ordinal).ok()?)
+    }
+}
+
+/// Encodes and decodes the values of an [`InversionMap`].
+pub trait ValueCodec<V> {
+    fn encode<E: Extend<u8>>(&self, value: &V, out: &mut E);
+    /// Decodes a value from the front of `bytes`, advancing it past the value. Returns `None` if
+    /// the bytes do not hold a valid value.
+    fn decode(&self, bytes: &mut &[u8]) -> Option<V>;
+}
+
+/// A [`ValueCodec`] storing integer values as LEB128 varints of their ordinal.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VarintCodec;
+
+impl<V: VarintIndex> ValueCodec<V> for VarintCodec {
+    fn encode<E: Extend<u8>>(&self, value: &V, out: &mut E) {
+        write_varint(out, value.to_ordinal());
+    }
+
+    fn decode(&self, bytes: &mut &[u8]) -> Option<V> {
+        V::from_ordinal(read_varint(bytes).ok()?)
+    }
+}
+
+// The codec of lists, which have no values to store.
+#[derive(Clone, Copy, Debug)]
+struct UnitCodec;
+
+impl ValueCodec<()> for UnitCodec {
+    fn encode<E: Extend<u8>>(&self, _: &(), _: &mut E) {}
+
+    fn decode(&self, _: &mut &[u8]) -> Option<()> {
+        Some(())
+    }
+}
+
+/// The error returned when decoding malformed input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input ended in the middle of the encoding.
+    UnexpectedEnd,
+    /// The tag byte did not match the kind of collection being decoded.
+    InvalidTag,
+    /// The input was encoded for an index type of a different [`IndexKind`].
+    IndexKindMismatch,
+    /// The input was encoded for an index type of a different width.
+    IndexWidthMismatch,
+    /// A varint was overlong or a range reached past [`OrderedIndex::max_value()`].
+    Overflow,
+    /// The value codec rejected a value.
+    InvalidValue,
+    /// There were bytes left after the last range.
+    TrailingBytes,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DecodeError::UnexpectedEnd => "unexpected end of input",
+            DecodeError::InvalidTag => "invalid tag",
+            DecodeError::IndexKindMismatch => "index kind mismatch",
+            DecodeError::IndexWidthMismatch => "index width mismatch",
+            DecodeError::Overflow => "varint or range overflow",
+            DecodeError::InvalidValue => "invalid value",
+            DecodeError::TrailingBytes => "trailing bytes",
+        })
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+fn write_varint<E: Extend<u8>>(out: &mut E, mut value: u128) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.extend([byte]);
+            return;
+        }
+        out.extend([byte | 0x80]);
+    }
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u128, DecodeError> {
+    let mut value = 0u128;
+    let mut shift = 0;
+    loop {
+        let (&byte, rest) = bytes.split_first().ok_or(DecodeError::UnexpectedEnd)?;
+        *bytes = rest;
+        let bits = (byte & 0x7F) as u128;
+        // reject bits shifted out of the value as well as overlong zero padding
+        if shift >= 128 || (bits << shift) >> shift != bits || (byte == 0 && shift > 0) {
+            return Err(DecodeError::Overflow);
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(feature = "alloc")]
+fn encode_entries<'a, Idx: VarintIndex, V: 'a, C: ValueCodec<V>>(
+    out: &mut Vec<u8>,
+    tag: u8,
+    len: usize,
+    entries: impl Iterator<Item = (RangeInclusive<Idx>, &'a V)>,
+    codec: &C,
+) {
+    out.extend([tag, Idx::KIND as u8, size_of::<Idx>() as u8]);
+    write_varint(out, len as u128);
+    let mut next = Some(0);
+    for (range, value) in entries {
+        let (start, end) = (range.start().to_ordinal(), range.end().to_ordinal());
+        // ranges are sorted and non-overlapping, so only the last range can end at the maximum
+        write_varint(out, start - next.unwrap());
+        write_varint(out, end - start);
+        codec.encode(value, out);
+        next = end.checked_add(1);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<Idx: VarintIndex> InversionList<Idx> {
+    /// Appends the binary encoding of this list to `out`.
+    pub fn encode_into(&self, out: &mut Vec<u8>) {
+        encode_entries(
+            out,
+            LIST_TAG,
+            self.len(),
+            self.iter().map(|range| (range, &())),
+            &UnitCodec,
+        );
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<Idx: VarintIndex, V> InversionMap<Idx, V> {
+    /// Appends the binary encoding of this map to `out`, with the values encoded by `codec`.
+    pub fn encode_into<C: ValueCodec<V>>(&self, out: &mut Vec<u8>, codec: &C) {
+        encode_entries(out, MAP_TAG, self.len(), self.iter(), codec);
+    }
+}
+
+/// A validated binary encoding of an [`InversionMap`], decoded lazily.
+#[derive(Clone, Copy, Debug)]
+pub struct EncodedMap<'a, Idx, V, C> {
+    entries: &'a [u8],
+    len: usize,
+    codec: C,
+    _marker: PhantomData<fn() -> (Idx, V)>,
+}
+
+impl<'a, Idx: VarintIndex, V, C: ValueCodec<V>> EncodedMap<'a, Idx, V, C> {
+    /// Validates the encoded map in `bytes` without allocating.
+    pub fn decode(bytes: &'a [u8], codec: C) -> Result<Self, DecodeError> {
+        Self::decode_tagged(bytes, MAP_TAG, codec)
+    }
+
+    fn decode_tagged(mut bytes: &'a [u8], tag: u8, codec: C) -> Result<Self, DecodeError> {
+        match *bytes {
+            [t, kind, width, ref rest @ ..] => {
+                if t != tag {
+                    return Err(DecodeError::InvalidTag);
+                }
+                if kind != Idx::KIND as u8 {
+                    return Err(DecodeError::IndexKindMismatch);
+                }
+                if width as usize != size_of::<Idx>() {
+                    return Err(DecodeError::IndexWidthMismatch);
+                }
+                bytes = rest;
+            }
+            _ => return Err(DecodeError::UnexpectedEnd),
+        }
+        let len = usize::try_from(read_varint(&mut bytes)?).map_err(|_| DecodeError::Overflow)?;
+        let map = EncodedMap {
+            entries: bytes,
+            len,
+            codec,
+            _marker: PhantomData,
+        };
+        let mut iter = map.raw_iter();
+        while iter.remaining > 0 {
+            iter.decode_entry()?;
+        }
+        if !iter.bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        Ok(map)
+    }
+
+    /// Returns the number of ranges in the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> MapIter<'_, Idx, V, C> {
+        MapIter {
+            raw: self.raw_iter(),
+        }
+    }
+
+    /// Decodes the ranges and values into an owned [`InversionMap`].
+    #[cfg(feature = "alloc")]
+    pub fn to_map(&self) -> InversionMap<Idx, V> {
+        InversionMap {
+            ranges: self
+                .iter()
+                .map(|(range, value)| Entry {
+                    start: *range.start(),
+                    end: *range.end(),
+                    value,
+                })
+                .collect(),
+        }
+    }
+
+    fn raw_iter(&self) -> RawIter<'_, Idx, V, C> {
+        RawIter {
+            bytes: self.entries,
+            remaining: self.len,
+            next: Some(0),
+            codec: &self.codec,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// A validated binary encoding of an [`InversionList`], decoded lazily.
+#[derive(Clone, Copy, Debug)]
+pub struct EncodedList<'a, Idx> {
+    map: EncodedMap<'a, Idx, (), UnitCodec>,
+}
+
+impl<'a, Idx: VarintIndex> EncodedList<'a, Idx> {
+    /// Validates the encoded list in `bytes` without allocating.
+    pub fn decode(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        EncodedMap::decode_tagged(bytes, LIST_TAG, UnitCodec).map(|map| EncodedList { map })
+    }
+
+    /// Returns the number of ranges in the list.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn iter(&self) -> ListIter<'_, Idx> {
+        ListIter {
+            raw: self.map.raw_iter(),
+        }
+    }
+
+    /// Decodes the ranges into an owned [`InversionList`].
+    #[cfg(feature = "alloc")]
+    pub fn to_list(&self) -> InversionList<Idx> {
+        InversionList::from_map(self.map.to_map())
+    }
+}
+
+// Decodes the entries following the header, shared by validation and iteration.
+struct RawIter<'e, Idx, V, C> {
+    bytes: &'e [u8],
+    remaining: usize,
+    // the ordinal the next range may start at, `None` once a range ended at the maximum
+    next: Option<u128>,
+    codec: &'e C,
+    _marker: PhantomData<fn() -> (Idx, V)>,
+}
+
+impl<Idx: VarintIndex, V, C: ValueCodec<V>> RawIter<'_, Idx, V, C> {
+    fn decode_entry(&mut self) -> Result<(RangeInclusive<Idx>, V), DecodeError> {
+        let gap = read_varint(&mut self.bytes)?;
+        let span = read_varint(&mut self.bytes)?;
+        let start = self
+            .next
+            .and_then(|next| next.checked_add(gap))
+            .ok_or(DecodeError::Overflow)?;
+        let end = start.checked_add(span).ok_or(DecodeError::Overflow)?;
+        let range = Idx::from_ordinal(start).ok_or(DecodeError::Overflow)?
+            ..=Idx::from_ordinal(end).ok_or(DecodeError::Overflow)?;
+        let value = self
+            .codec
+            .decode(&mut self.bytes)
+            .ok_or(DecodeError::InvalidValue)?;
+        self.next = end.checked_add(1);
+        self.remaining -= 1;
+        Ok((range, value))
+    }
+
+    fn next_entry(&mut self) -> Option<(RangeInclusive<Idx>, V)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        // the input got validated when decoding the header
+        Some(
+            self.decode_entry()
+                .expect("validated encoding failed to decode"),
+        )
+    }
+}
+
+/// An iterator over the ranges and values of an [`EncodedMap`].
+pub struct MapIter<'e, Idx, V, C> {
+    raw: RawIter<'e, Idx, V, C>,
+}
+
+impl<Idx: VarintIndex, V, C: ValueCodec<V>> Iterator for MapIter<'_, Idx, V, C> {
+    type Item = (RangeInclusive<Idx>, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.raw.next_entry()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.raw.remaining, Some(self.raw.remaining))
+    }
+}
+
+impl<Idx: VarintIndex, V, C: ValueCodec<V>> FusedIterator for MapIter<'_, Idx, V, C> {}
+impl<Idx: VarintIndex, V, C: ValueCodec<V>> ExactSizeIterator for MapIter<'_, Idx, V, C> {}
+
+/// An iterator over the ranges of an [`EncodedList`].
+pub struct ListIter<'e, Idx> {
+    raw: RawIter<'e, Idx, (), UnitCodec>,
+}
+
+impl<Idx: VarintIndex> Iterator for ListIter<'_, Idx> {
+    type Item = RangeInclusive<Idx>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.raw.next_entry().map(|(range, ())| range)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.raw.remaining, Some(self.raw.remaining))
+    }
+}
+
+impl<Idx: VarintIndex> FusedIterator for ListIter<'_, Idx> {}
+impl<Idx: VarintIndex> ExactSizeIterator for ListIter<'_, Idx> {}
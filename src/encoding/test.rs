@@ -0,0 +1,135 @@
+use super::*;
+
+const U: u8 = IndexKind::Unsigned as u8;
+
+#[test]
+fn varint() {
+    for value in [0, 1, 127, 128, 300, u64::MAX as u128, u128::MAX] {
+        let mut bytes = Vec::new();
+        write_varint(&mut bytes, value);
+        let mut slice = bytes.as_slice();
+        assert_eq!(read_varint(&mut slice), Ok(value));
+        assert!(slice.is_empty());
+    }
+    assert_eq!(
+        read_varint(&mut &[0x80][..]),
+        Err(DecodeError::UnexpectedEnd)
+    );
+    // overlong zero padding
+    assert_eq!(
+        read_varint(&mut &[0x81, 0x00][..]),
+        Err(DecodeError::Overflow)
+    );
+    let mut too_wide = [0xFF; 19];
+    too_wide[18] = 0x04;
+    assert_eq!(read_varint(&mut &too_wide[..]), Err(DecodeError::Overflow));
+}
+
+#[test]
+fn decode_list() {
+    // 0..=9, 20..=255
+    let bytes = [LIST_TAG, U, 1, 2, 0, 9, 10, 235, 1];
+    let list = EncodedList::<u8>::decode(&bytes).unwrap();
+    assert_eq!(list.len(), 2);
+    assert!(list.iter().eq([0..=9, 20..=255]));
+}
+
+#[test]
+fn decode_errors() {
+    let decode = |bytes: &[u8]| EncodedList::<u8>::decode(bytes).map(|_| ());
+    assert_eq!(decode(&[]), Err(DecodeError::UnexpectedEnd));
+    assert_eq!(decode(&[MAP_TAG, U, 1, 0]), Err(DecodeError::InvalidTag));
+    assert_eq!(
+        decode(&[LIST_TAG, IndexKind::Signed as u8, 1, 0]),
+        Err(DecodeError::IndexKindMismatch)
+    );
+    assert_eq!(
+        decode(&[LIST_TAG, U, 4, 0]),
+        Err(DecodeError::IndexWidthMismatch)
+    );
+    assert_eq!(
+        decode(&[LIST_TAG, U, 1, 1, 0]),
+        Err(DecodeError::UnexpectedEnd)
+    );
+    assert_eq!(
+        decode(&[LIST_TAG, U, 1, 1, 0, 0, 0]),
+        Err(DecodeError::TrailingBytes)
+    );
+    // a range past `u8::MAX`
+    assert_eq!(
+        decode(&[LIST_TAG, U, 1, 1, 100, 0xC8, 0x01]),
+        Err(DecodeError::Overflow)
+    );
+    // a second range after one ending at `u8::MAX`
+    assert_eq!(
+        decode(&[LIST_TAG, U, 1, 2, 0, 0xFF, 1, 0, 0]),
+        Err(DecodeError::Overflow)
+    );
+    assert_eq!(
+        EncodedMap::<u8, u8, _>::decode(&[MAP_TAG, U, 1, 1, 0, 0, 0xAC, 0x02], VarintCodec)
+            .map(|_| ()),
+        Err(DecodeError::InvalidValue)
+    );
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn list_round_trip() {
+    let mut il = InversionList::<u8>::new();
+    il.insert_range(0..10);
+    il.insert_range(10..20);
+    il.insert_range(200..);
+    let mut bytes = Vec::new();
+    il.encode_into(&mut bytes);
+    assert_eq!(bytes, [LIST_TAG, U, 1, 2, 0, 19, 0xB4, 0x01, 55]);
+    assert_eq!(EncodedList::decode(&bytes).unwrap().to_list(), il);
+    // adjacent ranges are valid input, but get merged by `to_list`
+    let adjacent = [LIST_TAG, U, 1, 3, 0, 9, 0, 9, 0xB4, 0x01, 55];
+    let decoded = EncodedList::<u8>::decode(&adjacent).unwrap();
+    assert_eq!(decoded.iter().count(), 3);
+    assert_eq!(decoded.to_list(), il);
+
+    let il = InversionList::from_chars("az\u{D7FF}\u{E000}\u{10FFFF}");
+    bytes.clear();
+    il.encode_into(&mut bytes);
+    let decoded = EncodedList::<char>::decode(&bytes).unwrap();
+    assert_eq!(decoded.to_list(), il);
+    // `char` ordinals skip the surrogates, so they must not be read as integers of the same width
+    assert_eq!(
+        EncodedList::<u32>::decode(&bytes).map(|_| ()),
+        Err(DecodeError::IndexKindMismatch)
+    );
+    assert_eq!(
+        EncodedList::<i32>::decode(&bytes).map(|_| ()),
+        Err(DecodeError::IndexKindMismatch)
+    );
+    // the surrogate gap is skipped, so `\u{D7FF}` and `\u{E000}` form one range
+    assert!(decoded.iter().eq([
+        'a'..='a',
+        'z'..='z',
+        '\u{D7FF}'..='\u{E000}',
+        '\u{10FFFF}'..='\u{10FFFF}'
+    ]));
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn map_round_trip() {
+    let mut im = InversionMap::<i16, u64>::new();
+    im.insert_range(i16::MIN..-100, 1);
+    im.insert_range(-5..=5, u64::MAX);
+    im.insert_range(1000.., 0);
+    let mut bytes = Vec::new();
+    im.encode_into(&mut bytes, &VarintCodec);
+    let decoded = EncodedMap::<i16, u64, _>::decode(&bytes, VarintCodec).unwrap();
+    assert_eq!(decoded.len(), 3);
+    assert_eq!(decoded.to_map(), im);
+    assert_eq!(
+        EncodedList::<i16>::decode(&bytes).map(|_| ()),
+        Err(DecodeError::InvalidTag)
+    );
+    assert_eq!(
+        EncodedMap::<u16, u64, _>::decode(&bytes, VarintCodec).map(|_| ()),
+        Err(DecodeError::IndexKindMismatch)
+    );
+}
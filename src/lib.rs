@@ -1,12 +1,48 @@
 #![cfg_attr(not(test), no_std)]
+#[cfg(feature = "alloc")]
 extern crate alloc;
+// lets the code emitted by `codegen` refer to this crate by name in the tests
+#[cfg(test)]
+extern crate self as inversion_list;
 
+pub mod array;
+#[cfg(feature = "alloc")]
+pub mod boundary;
+#[cfg(feature = "alloc")]
+pub mod btree;
+#[cfg(feature = "alloc")]
+pub mod codegen;
+pub mod encoding;
+#[cfg(feature = "alloc")]
 pub mod list;
+#[cfg(feature = "alloc")]
 pub mod map;
+#[cfg(feature = "alloc")]
+pub mod parse;
+#[cfg(feature = "alloc")]
+pub mod persistent;
+pub mod static_list;
+#[cfg(all(test, feature = "alloc"))]
+mod testing;
+#[cfg(feature = "alloc")]
+pub mod ucd;
 
+pub use self::array::{ArrayInversionList, ArrayInversionMap};
+#[cfg(feature = "alloc")]
+pub use self::boundary::BoundaryList;
+#[cfg(feature = "alloc")]
+pub use self::btree::BTreeInversionMap;
+#[cfg(feature = "alloc")]
 pub use self::list::InversionList;
+#[cfg(feature = "alloc")]
 pub use self::map::InversionMap;
+#[cfg(feature = "alloc")]
+pub use self::persistent::PersistentInversionMap;
+pub use self::static_list::StaticInversionList;
 
+mod raw;
+#[cfg(feature = "serde")]
+mod serde_impl;
 mod util;
 
 pub trait OrderedIndex: Sized + Copy + PartialOrd + Ord + Eq + core::fmt::Debug {
@@ -15,6 +51,9 @@ pub trait OrderedIndex: Sized + Copy + PartialOrd + Ord + Eq + core::fmt::Debug
     fn max_value() -> Self;
     fn checked_add(self, v: Self) -> Option<Self>;
     fn checked_sub(self, v: Self) -> Option<Self>;
+    /// Returns the number of steps of [`OrderedIndex::one()`] it takes to get from `start` to
+    /// `end`, or `None` if `end` lies before `start`.
+    fn steps_between(start: Self, end: Self) -> Option<u128>;
 }
 
 macro_rules! impl_prim {
@@ -30,8 +69,60 @@ macro_rules! impl_prim {
                 fn checked_sub(self, v: Self) -> Option<Self> {
                     Self::checked_sub(self, v)
                 }
+                fn steps_between(start: Self, end: Self) -> Option<u128> {
+                    // sign extension keeps the wrapping difference exact for signed types
+                    (start <= end).then(|| (end as u128).wrapping_sub(start as u128))
+                }
             }
         )*
     };
 }
 impl_prim! { u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize }
+
+// Surrogate code points are not valid `char`s, so the index space of `char` skips over them.
+const SURROGATE_START: u32 = 0xD800;
+const SURROGATE_LEN: u32 = 0x800;
+
+/// Maps a `char` to its position in the gapless `char` index space.
+fn char_to_index(c: char) -> u32 {
+    let c = c as u32;
+    if c < SURROGATE_START {
+        c
+    } else {
+        c - SURROGATE_LEN
+    }
+}
+
+/// Inverse of [`char_to_index`].
+fn index_to_char(idx: u32) -> Option<char> {
+    if idx < SURROGATE_START {
+        char::from_u32(idx)
+    } else {
+        char::from_u32(idx.checked_add(SURROGATE_LEN)?)
+    }
+}
+
+/// `char`s are indexed by their scalar value with the surrogate range `0xD800..=0xDFFF` skipped,
+/// so stepping by [`OrderedIndex::one()`] from `'\u{D7FF}'` yields `'\u{E000}'`.
+impl OrderedIndex for char {
+    fn one() -> Self {
+        '\u{1}'
+    }
+    fn min_value() -> Self {
+        '\0'
+    }
+    fn max_value() -> Self {
+        char::MAX
+    }
+    fn checked_add(self, v: Self) -> Option<Self> {
+        index_to_char(char_to_index(self).checked_add(char_to_index(v))?)
+    }
+    fn checked_sub(self, v: Self) -> Option<Self> {
+        index_to_char(char_to_index(self).checked_sub(char_to_index(v))?)
+    }
+    fn steps_between(start: Self, end: Self) -> Option<u128> {
+        char_to_index(end)
+            .checked_sub(char_to_index(start))
+            .map(u128::from)
+    }
+}
@@ -1,24 +1,53 @@
+use core::cmp::Ordering;
 use core::iter::FromIterator;
-use core::ops::{Range, RangeBounds};
-use core::{mem, ops};
+use core::ops;
+use core::ops::{RangeBounds, RangeInclusive};
 
 use alloc::vec::Vec;
 
 use crate::map::{EntriesRef, Entry};
+use crate::util::{bounds_to_range, predecessor, range_count, successor};
 use crate::{InversionMap, OrderedIndex};
 
+#[cfg(test)]
+mod test;
+
+mod chars;
+mod class;
 mod iter;
+mod merge;
+mod rank;
+mod split;
+
+pub use self::class::{ClassParseError, ClassParseErrorKind};
+pub use self::iter::{
+    Difference, Elements, Gaps, Intersection, IntoIter, Iter, Range, SymmetricDifference, Union,
+};
+pub use self::split::SplitInversionList;
 
 /// An inversion list is a data structure that describes a set of non-overlapping numeric ranges, stored in increasing order.
 ///
 /// A few notes regarding the naming convention of the functions:
 /// - *_strict: These functions usual check that ranges are strictly the same, and not sub/supersets.
 /// - *_at: These functions usually take indices into the backing buffer, while the other versions
-///         generally take a value that is contained in a range or ranges directly.
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
-pub struct InversionList<Idx: OrderedIndex = usize>(InversionMap<Idx, ()>);
+///   generally take a value that is contained in a range or ranges directly.
+///
+/// Ranges are handed out as [`RangeInclusive`]s so that lists can cover
+/// [`OrderedIndex::max_value()`].
+///
+/// A list is always kept canonical, directly adjacent ranges are merged, so every set of indices
+/// has exactly one representation. This makes the [`Eq`], [`Hash`](core::hash::Hash) and [`Ord`]
+/// impls compare the covered sets. Use a [`SplitInversionList`] to work with split ranges.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct InversionList<Idx: OrderedIndex = usize>(pub(crate) InversionMap<Idx, ()>);
 
 impl<Idx: OrderedIndex> InversionList<Idx> {
+    /// Wraps the given map, merging adjacent ranges that it may hold.
+    pub(crate) fn from_map(mut map: InversionMap<Idx, ()>) -> Self {
+        map.coalesce();
+        InversionList(map)
+    }
+
     pub fn new() -> Self {
         InversionList(InversionMap::new())
     }
@@ -43,6 +72,7 @@ impl<Idx: OrderedIndex> InversionList<Idx> {
         self.0.is_empty()
     }
 
+    /// Returns the last index covered by the list, this is an inclusive bound.
     pub fn end(&self) -> Option<Idx> {
         self.0.end()
     }
@@ -52,15 +82,22 @@ impl<Idx: OrderedIndex> InversionList<Idx> {
     }
 
     /// Returns the complete surrounding range, if any.
-    pub fn span(&self) -> Option<Range<Idx>> {
+    pub fn span(&self) -> Option<RangeInclusive<Idx>> {
         self.0.span()
     }
 
-    pub fn first(&self) -> Option<Range<Idx>> {
+    /// Returns the number of indices covered by the list, saturating at `u128::MAX`.
+    ///
+    /// This differs from [`Self::len`], which returns the number of ranges.
+    pub fn count(&self) -> u128 {
+        self.0.count()
+    }
+
+    pub fn first(&self) -> Option<RangeInclusive<Idx>> {
         self.0.first().map(|(range, _)| range)
     }
 
-    pub fn last(&self) -> Option<Range<Idx>> {
+    pub fn last(&self) -> Option<RangeInclusive<Idx>> {
         self.0.last().map(|(range, _)| range)
     }
 
@@ -71,11 +108,11 @@ impl<Idx: OrderedIndex> InversionList<Idx> {
 
     /// Checks whether this InversionList contains a range that is a "superrange" of the given range.
     pub fn contains_range<R: RangeBounds<Idx>>(&self, range: R) -> bool {
-        self.lookup_range(range).map_or(false, |it| !it.is_empty())
+        self.0.contains_range(range)
     }
 
     /// Looks up the range the given index is part of if it is contained within the list.
-    pub fn lookup(&self, index: Idx) -> Option<Range<Idx>> {
+    pub fn lookup(&self, index: Idx) -> Option<RangeInclusive<Idx>> {
         self.0.lookup(index).map(|(range, _)| range)
     }
 
@@ -107,106 +144,127 @@ impl<Idx: OrderedIndex> InversionList<Idx> {
         }
     }
 
-    /// Adds a unit range(index..index + 1) to the inversion list. This is faster than using
-    /// [`insert_range`] saving a second binary_search.
+    /// Returns the number of indices covered by both `self` and `other`, without allocating.
+    pub fn intersection_count(&self, other: &Self) -> u128 {
+        sum_counts(self.intersection(other))
+    }
+
+    /// Returns the number of indices covered by `self`, `other` or both, without allocating.
+    pub fn union_count(&self, other: &Self) -> u128 {
+        sum_counts(self.union(other))
+    }
+
+    /// Returns the number of indices covered by `self` but not by `other`, without allocating.
+    pub fn difference_count(&self, other: &Self) -> u128 {
+        sum_counts(self.difference(other))
+    }
+
+    /// Adds a unit range(index..=index) to the inversion list.
     ///
     /// If the unit is not part of an existing range, `true` is returned.
     ///
     /// If the unit already exists in a range, `false` is returned.
-    ///
-    /// # Panics
-    ///
-    /// Panics if index is equal to usize::MAX.
     pub fn insert_unit(&mut self, index: Idx) -> bool {
-        self.0.insert_unit(index, ())
+        let inserted = self.0.insert_unit(index, ());
+        self.0.coalesce_around(index..=index);
+        inserted
     }
 
     pub fn insert_range<R: RangeBounds<Idx>>(&mut self, range: R) {
-        self.0.insert_range_with(range, |_| ());
+        if let Some(range) = bounds_to_range(range) {
+            self.0.insert_range_with(range.clone(), |_| ());
+            self.0.coalesce_around(range);
+        }
     }
 
     pub fn add_unit(&mut self, index: Idx) -> bool {
-        self.0.add_unit(index, ())
+        let inserted = self.0.add_unit(index, ());
+        self.0.coalesce_around(index..=index);
+        inserted
     }
 
     pub fn add_range<R: RangeBounds<Idx>>(&mut self, range: R) {
-        self.0.add_range_with(range, |_| ());
+        if let Some(range) = bounds_to_range(range) {
+            self.0.add_range_with(range.clone(), |_| ());
+            self.0.coalesce_around(range);
+        }
     }
 
     pub fn remove_range<R: RangeBounds<Idx>>(&mut self, range: R) {
         self.0.remove_range(range, |_, _| (), |_, _| ());
     }
 
-    /// Splits the range that contains `at` in two with `at` being the split point.
-    ///
-    /// If a range exists that contains `at` the return value are the indices of the
-    /// new left and right ranges of the split point. The left range will contain `at`.
-    /// If `at` is equal to the start of the range it is in, no split occurs and the left
-    /// and right indices will be equal to the index of the range containing the value.
-    ///
-    /// Split ranges that are right next to each other will not be recognized as one.
-    /// Meaning functions like `contains_range` will not work properly if the start and end
-    /// points lie in the different parts of the neighbouring ranges. Thus it is important to
-    /// either remove these ranges or remerge them.
-    pub fn split(&mut self, at: Idx) {
-        self.0.split(at);
-    }
-
-    /// Merges the ranges at `start` and `end`, discarding all ranges inbetween them.
-    ///
-    /// # Panics
+    /// Returns the indices inside of `bounds` that are not covered by this list.
     ///
-    /// Panics if the indices dont point to a valid index into the vec.
-    pub fn merge(&mut self, start: usize, end: usize) {
-        self.0.ranges[start].range.end = self.0.ranges[end].range.end;
-        self.0.ranges.drain(start + 1..=end);
-    }
-
-    /// Merges all ranges together that are directly adjacent to each other.
-    pub fn collapse(&mut self) {
-        let ranges = &mut self.0.ranges;
-        let mut i = 1;
-        while i < ranges.len() {
-            if ranges[i - 1].range.end == ranges[i].range.start {
-                ranges[i - 1].range.end = ranges[i].range.end;
-                ranges.remove(i);
-            } else {
-                i += 1;
-            }
-        }
+    /// Unlike [`Not`](ops::Not), this only allocates for the ranges inside of `bounds`.
+    pub fn complement_within<R: RangeBounds<Idx>>(&self, bounds: R) -> Self {
+        self.0.complement_within(bounds)
     }
 
     /// Inverts all ranges, meaning existing ranges will be removed and parts that were previously
     /// not covered by ranges will now be covered.
     pub fn invert(&mut self) {
-        let prev_len = self.0.len();
-        let mut old = mem::replace(&mut self.0.ranges, Vec::with_capacity(prev_len)).into_iter();
-
-        let mut last = match old.next() {
-            Some(range) if range.range.start == Idx::min_value() => range.range.end,
-            Some(range) => {
-                self.0.ranges.push(Entry {
-                    range: Idx::min_value()..range.range.start,
-                    value: (),
-                });
-                range.range.end
+        *self = !&*self;
+    }
+}
+
+impl<Idx: OrderedIndex> PartialOrd for InversionList<Idx> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Lists are ordered like the sorted sequences of the indices they cover, the same way
+/// [`BTreeSet`](alloc::collections::BTreeSet)s are.
+impl<Idx: OrderedIndex> Ord for InversionList<Idx> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let mut lhs = self.0.ranges.iter().peekable();
+        let mut rhs = other.0.ranges.iter().peekable();
+        loop {
+            let (l, r) = match (lhs.next(), rhs.next()) {
+                (Some(l), Some(r)) => (l, r),
+                (l, r) => return l.is_some().cmp(&r.is_some()),
+            };
+            if l.start != r.start {
+                return l.start.cmp(&r.start);
             }
-            None => return,
-        };
-        for range in old {
-            if range.range.start != last {
-                self.0.ranges.push(Entry {
-                    range: last..range.range.start,
-                    value: (),
-                });
+            // the shorter range continues with a gap, as the list is canonical, so the sequence it
+            // belongs to is the greater one unless it ends right there
+            match l.end.cmp(&r.end) {
+                Ordering::Less => {
+                    return match lhs.peek() {
+                        Some(_) => Ordering::Greater,
+                        None => Ordering::Less,
+                    }
+                }
+                Ordering::Greater => {
+                    return match rhs.peek() {
+                        Some(_) => Ordering::Less,
+                        None => Ordering::Greater,
+                    }
+                }
+                Ordering::Equal => {}
             }
-            last = range.range.end;
         }
     }
 }
 
-impl<Idx: OrderedIndex> FromIterator<Range<Idx>> for InversionList<Idx> {
-    fn from_iter<T: IntoIterator<Item = Range<Idx>>>(iter: T) -> Self {
+fn sum_counts<Idx: OrderedIndex>(ranges: impl Iterator<Item = RangeInclusive<Idx>>) -> u128 {
+    ranges.fold(0, |acc, range| acc.saturating_add(range_count(&range)))
+}
+
+impl<Idx: OrderedIndex> FromIterator<ops::Range<Idx>> for InversionList<Idx> {
+    fn from_iter<T: IntoIterator<Item = ops::Range<Idx>>>(iter: T) -> Self {
+        let mut res = InversionList::new();
+        for range in iter {
+            res.insert_range(range);
+        }
+        res
+    }
+}
+
+impl<Idx: OrderedIndex> FromIterator<RangeInclusive<Idx>> for InversionList<Idx> {
+    fn from_iter<T: IntoIterator<Item = RangeInclusive<Idx>>>(iter: T) -> Self {
         let mut res = InversionList::new();
         for range in iter {
             res.insert_range(range);
@@ -225,193 +283,115 @@ impl<Idx: OrderedIndex> ops::Not for InversionList<Idx> {
 impl<Idx: OrderedIndex> ops::Not for &InversionList<Idx> {
     type Output = InversionList<Idx>;
     fn not(self) -> InversionList<Idx> {
-        let mut ranges = Vec::with_capacity(self.capacity());
-        let mut iter = self.iter();
-        let Some(range) = iter.next() else {
-            return InversionList(InversionMap {
-                ranges: alloc::vec![Entry {
-                    range: Idx::min_value()..Idx::max_value(),
-                    value: ()
-                }],
-            });
-        };
-        let mut last = if range.start == Idx::min_value() {
-            if range.end == Idx::max_value() {
-                return InversionList::new();
+        let mut ranges = Vec::with_capacity(self.len() + 1);
+        // the next index that is not covered by a previous range, `None` if we reached the end
+        let mut next = Some(Idx::min_value());
+        for range in self.iter() {
+            let (start, end) = range.into_inner();
+            if let Some(gap_start) = next {
+                if gap_start < start {
+                    ranges.push(Entry {
+                        // `gap_start < start` so this can't underflow
+                        start: gap_start,
+                        end: predecessor(start).unwrap(),
+                        value: (),
+                    });
+                }
             }
-            range.end
-        } else {
-            ranges.push(Entry {
-                range: Idx::min_value()..range.start,
-                value: (),
-            });
-            range.end
-        };
-        for range in iter {
+            next = successor(end);
+        }
+        if let Some(start) = next {
             ranges.push(Entry {
-                range: last..range.start,
+                start,
+                end: Idx::max_value(),
                 value: (),
             });
-            last = range.end
-        }
-        ranges.push(Entry {
-            range: last..Idx::max_value(),
-            value: (),
-        });
-        InversionList(InversionMap { ranges })
-    }
-}
-
-impl<Idx: OrderedIndex> ops::BitOr for InversionList<Idx> {
-    type Output = Self;
-
-    fn bitor(self, rhs: Self) -> Self::Output {
-        if self.len() < rhs.len() {
-            rhs | &self
-        } else {
-            self | &rhs
         }
+        InversionList(InversionMap {
+            ranges: ranges.into(),
+        })
     }
 }
-
-impl<Idx: OrderedIndex> ops::BitOr<&InversionList<Idx>> for InversionList<Idx> {
-    type Output = Self;
-
-    fn bitor(mut self, rhs: &Self) -> Self::Output {
-        for range in rhs {
-            self.add_range(range);
-        }
-
-        self
+impl<Idx: OrderedIndex> ops::BitOr<&InversionList<Idx>> for &InversionList<Idx> {
+    type Output = InversionList<Idx>;
+    fn bitor(self, rhs: &InversionList<Idx>) -> Self::Output {
+        InversionList(InversionMap {
+            ranges: merge::union(&self.0.ranges, &rhs.0.ranges).into(),
+        })
     }
 }
 
-impl<Idx: OrderedIndex> ops::BitOr<InversionList<Idx>> for &InversionList<Idx> {
+impl<Idx: OrderedIndex> ops::BitAnd<&InversionList<Idx>> for &InversionList<Idx> {
     type Output = InversionList<Idx>;
-
-    fn bitor(self, rhs: InversionList<Idx>) -> Self::Output {
-        rhs | self
+    fn bitand(self, rhs: &InversionList<Idx>) -> Self::Output {
+        InversionList(InversionMap {
+            ranges: merge::intersection(&self.0.ranges, &rhs.0.ranges).into(),
+        })
     }
 }
 
-impl<Idx: OrderedIndex> ops::BitOrAssign for InversionList<Idx> {
-    fn bitor_assign(&mut self, rhs: Self) {
-        for range in rhs {
-            self.add_range(range);
-        }
+impl<Idx: OrderedIndex> ops::Sub<&InversionList<Idx>> for &InversionList<Idx> {
+    type Output = InversionList<Idx>;
+    fn sub(self, rhs: &InversionList<Idx>) -> Self::Output {
+        InversionList(InversionMap {
+            ranges: merge::difference(&self.0.ranges, &rhs.0.ranges).into(),
+        })
     }
 }
 
-impl<Idx: OrderedIndex> ops::BitOrAssign<&InversionList<Idx>> for InversionList<Idx> {
-    fn bitor_assign(&mut self, rhs: &InversionList<Idx>) {
-        for range in rhs {
-            self.add_range(range);
-        }
+impl<Idx: OrderedIndex> ops::BitXor<&InversionList<Idx>> for &InversionList<Idx> {
+    type Output = InversionList<Idx>;
+    fn bitxor(self, rhs: &InversionList<Idx>) -> Self::Output {
+        InversionList(InversionMap {
+            ranges: merge::symmetric_difference(&self.0.ranges, &rhs.0.ranges).into(),
+        })
     }
 }
 
-impl<Idx: OrderedIndex> ops::BitAnd<&InversionList<Idx>> for &InversionList<Idx> {
-    type Output = InversionList<Idx>;
-    fn bitand(self, rhs: &InversionList<Idx>) -> Self::Output {
-        use Err as Insert;
-        use Ok as Within;
-
-        let mut res = InversionList::new();
-
-        let (base, iter) = if self.len() < rhs.len() {
-            (rhs, self.iter())
-        } else {
-            (self, rhs.iter())
-        };
-
-        for range in iter {
-            // we fetch all ranges in base, that overlap with range from iter
-            match base.0.range_binary_search(range.clone()) {
-                #[cfg(debug_assertions)]
-                (Within(idx_s), Insert(idx_e)) if idx_s == idx_e => {
-                    unreachable!("range was empty and should've been filtered out")
-                }
-                // left side in bounds, right side out of bounds
-                (Within(idx_s), Insert(idx_e)) => {
-                    let slice = &base.0.ranges[idx_s..idx_e];
-                    match slice {
-                        [] => unreachable!("range was empty and should've been filtered out"),
-                        [left, middle @ ..] => {
-                            res.insert_range(range.start..left.range.end);
-                            middle.iter().for_each(|entry| {
-                                res.insert_range(entry.range.clone());
-                            });
-                        }
-                    }
-                }
-                (Insert(idx_s), Insert(idx_e)) => {
-                    base.0.ranges[idx_s..idx_e].iter().for_each(|entry| {
-                        res.insert_range(entry.range.clone());
-                    });
-                }
-                (Insert(idx_s), Within(idx_e)) => {
-                    let slice = &base.0.ranges[idx_s..=idx_e];
-                    match slice {
-                        [] => unreachable!(),
-                        [middle @ .., right] => {
-                            res.insert_range(right.range.start..range.end);
-                            middle.iter().for_each(|entry| {
-                                res.insert_range(entry.range.clone());
-                            });
-                        }
-                    }
-                }
-                // both sides in bounds
-                (Within(idx_s), Within(idx_e)) => {
-                    let slice = &base.0.ranges[idx_s..=idx_e];
-                    match slice {
-                        [] => unreachable!(),
-                        [_] => res.insert_range(range.clone()),
-                        [left, middle @ .., right] => {
-                            res.insert_range(range.start..left.range.end);
-                            middle.iter().for_each(|entry| {
-                                res.insert_range(entry.range.clone());
-                            });
-                            res.insert_range(right.range.start..range.end);
-                        }
-                    }
+/// Forwards the owned variants of a set operator, and its assigning variants, to the
+/// `&InversionList op &InversionList` implementation.
+macro_rules! forward_set_op {
+    ($($op:ident::$fn:ident, $op_assign:ident::$fn_assign:ident;)*) => {
+        $(
+            impl<Idx: OrderedIndex> ops::$op<&InversionList<Idx>> for InversionList<Idx> {
+                type Output = InversionList<Idx>;
+                fn $fn(self, rhs: &InversionList<Idx>) -> Self::Output {
+                    ops::$op::$fn(&self, rhs)
                 }
             }
-        }
-
-        res
-    }
-}
 
-impl<Idx: OrderedIndex> ops::BitAnd<&InversionList<Idx>> for InversionList<Idx> {
-    type Output = Self;
-    fn bitand(self, rhs: &InversionList<Idx>) -> Self::Output {
-        &self & rhs
-    }
-}
+            impl<Idx: OrderedIndex> ops::$op<InversionList<Idx>> for &InversionList<Idx> {
+                type Output = InversionList<Idx>;
+                fn $fn(self, rhs: InversionList<Idx>) -> Self::Output {
+                    ops::$op::$fn(self, &rhs)
+                }
+            }
 
-impl<Idx: OrderedIndex> ops::BitAnd<InversionList<Idx>> for &InversionList<Idx> {
-    type Output = InversionList<Idx>;
-    fn bitand(self, rhs: InversionList<Idx>) -> Self::Output {
-        self & &rhs
-    }
-}
+            impl<Idx: OrderedIndex> ops::$op<InversionList<Idx>> for InversionList<Idx> {
+                type Output = InversionList<Idx>;
+                fn $fn(self, rhs: InversionList<Idx>) -> Self::Output {
+                    ops::$op::$fn(&self, &rhs)
+                }
+            }
 
-impl<Idx: OrderedIndex> ops::BitAnd<InversionList<Idx>> for InversionList<Idx> {
-    type Output = InversionList<Idx>;
-    fn bitand(self, rhs: InversionList<Idx>) -> Self::Output {
-        self & &rhs
-    }
-}
+            impl<Idx: OrderedIndex> ops::$op_assign for InversionList<Idx> {
+                fn $fn_assign(&mut self, rhs: Self) {
+                    ops::$op_assign::$fn_assign(self, &rhs);
+                }
+            }
 
-impl<Idx: OrderedIndex> ops::BitAndAssign for InversionList<Idx> {
-    fn bitand_assign(&mut self, rhs: Self) {
-        *self &= &rhs;
-    }
+            impl<Idx: OrderedIndex> ops::$op_assign<&InversionList<Idx>> for InversionList<Idx> {
+                fn $fn_assign(&mut self, rhs: &InversionList<Idx>) {
+                    *self = ops::$op::$fn(&*self, rhs);
+                }
+            }
+        )*
+    };
 }
 
-impl<Idx: OrderedIndex> ops::BitAndAssign<&InversionList<Idx>> for InversionList<Idx> {
-    fn bitand_assign(&mut self, rhs: &InversionList<Idx>) {
-        *self = &*self & rhs;
-    }
+forward_set_op! {
+    BitOr::bitor, BitOrAssign::bitor_assign;
+    BitAnd::bitand, BitAndAssign::bitand_assign;
+    Sub::sub, SubAssign::sub_assign;
+    BitXor::bitxor, BitXorAssign::bitxor_assign;
 }
@@ -0,0 +1,37 @@
+use alloc::vec::Vec;
+
+use crate::list::InversionList;
+use crate::map::Entry;
+use crate::util::successor;
+use crate::InversionMap;
+
+impl InversionList<char> {
+    /// Creates a list containing every character of `s`.
+    pub fn from_chars(s: &str) -> Self {
+        let mut chars: Vec<char> = s.chars().collect();
+        chars.sort_unstable();
+        chars.dedup();
+
+        let mut ranges: Vec<Entry<char, ()>> = Vec::with_capacity(chars.len());
+        for c in chars {
+            match ranges.last_mut() {
+                Some(last) if successor(last.end) == Some(c) => last.end = c,
+                _ => ranges.push(Entry {
+                    start: c,
+                    end: c,
+                    value: (),
+                }),
+            }
+        }
+        InversionList(InversionMap {
+            ranges: ranges.into(),
+        })
+    }
+
+    /// Checks whether every character of `s` is contained in the list.
+    ///
+    /// This is trivially true for the empty string.
+    pub fn contains_all_chars(&self, s: &str) -> bool {
+        s.chars().all(|c| self.contains(c))
+    }
+}
@@ -0,0 +1,194 @@
+use core::fmt::{self, Write};
+use core::iter::Peekable;
+use core::str::CharIndices;
+
+use alloc::string::String;
+
+use crate::list::InversionList;
+use crate::util::successor;
+
+/// An error encountered while parsing a regex character class.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClassParseError {
+    /// The byte offset the error occurred at.
+    pub position: usize,
+    pub kind: ClassParseErrorKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClassParseErrorKind {
+    /// The class did not start with `[`.
+    ExpectedOpenBracket,
+    /// The input ended before the closing `]`.
+    UnclosedClass,
+    /// An escape sequence was unknown, malformed or named no valid `char`.
+    InvalidEscape,
+    /// A range ended before it started.
+    ReversedRange,
+    /// There was input left after the closing `]`.
+    TrailingInput,
+}
+
+impl fmt::Display for ClassParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self.kind {
+            ClassParseErrorKind::ExpectedOpenBracket => "expected `[`",
+            ClassParseErrorKind::UnclosedClass => "unclosed character class",
+            ClassParseErrorKind::InvalidEscape => "invalid escape sequence",
+            ClassParseErrorKind::ReversedRange => "range end lies before its start",
+            ClassParseErrorKind::TrailingInput => "trailing input after character class",
+        };
+        write!(f, "{} at position {}", msg, self.position)
+    }
+}
+
+impl core::error::Error for ClassParseError {}
+
+impl InversionList<char> {
+    /// Parses a regex bracket expression like `[a-zA-Z_\u{80}-\u{10FFFF}]` or `[^0-9]`.
+    ///
+    /// Besides literal characters and ranges, the escapes `\n`, `\t`, `\r`, `\f`, `\v`,
+    /// `\xHH`, `\x{H..}`, `\uHHHH` and `\u{H..}` are understood, as is escaping any ASCII
+    /// punctuation with a backslash. A `]` directly following the opening `[` or `[^`, and a `-`
+    /// at the start or end of the class are taken literally. Shorthand classes like `\d`, nested
+    /// classes and set operations are not supported.
+    pub fn from_regex_class(s: &str) -> Result<Self, ClassParseError> {
+        let mut chars = s.char_indices().peekable();
+        let error = |position, kind| Err(ClassParseError { position, kind });
+        if chars.next_if(|&(_, c)| c == '[').is_none() {
+            return error(0, ClassParseErrorKind::ExpectedOpenBracket);
+        }
+        let negated = chars.next_if(|&(_, c)| c == '^').is_some();
+
+        let mut list = InversionList::new();
+        let mut first = true;
+        loop {
+            let Some(&(position, c)) = chars.peek() else {
+                return error(s.len(), ClassParseErrorKind::UnclosedClass);
+            };
+            if c == ']' && !first {
+                chars.next();
+                break;
+            }
+            first = false;
+            let start = parse_class_char(&mut chars, s.len())?;
+            // a dash right in front of the closing bracket is a literal
+            let is_range = chars.peek().is_some_and(|&(_, c)| c == '-')
+                && chars.clone().nth(1).is_some_and(|(_, c)| c != ']');
+            let end = if is_range {
+                chars.next();
+                parse_class_char(&mut chars, s.len())?
+            } else {
+                start
+            };
+            if end < start {
+                return error(position, ClassParseErrorKind::ReversedRange);
+            }
+            list.add_range(start..=end);
+        }
+        if let Some((position, _)) = chars.next() {
+            return error(position, ClassParseErrorKind::TrailingInput);
+        }
+        Ok(if negated { !list } else { list })
+    }
+
+    /// Writes the list as a regex bracket expression, choosing the shorter of the plain and the
+    /// negated form.
+    ///
+    /// Only `\`, `]`, `[`, `^` and `-` are escaped with a backslash and all characters besides
+    /// printable ASCII are written as `\x{H..}` escapes, so the result parses back with
+    /// [`Self::from_regex_class`] and common regex engines.
+    pub fn to_regex_class(&self) -> String {
+        let mut plain = String::from("[");
+        write_class_ranges(&mut plain, self).unwrap();
+        plain.push(']');
+
+        let complement = !self;
+        if complement.is_empty() {
+            return plain;
+        }
+        let mut negated = String::from("[^");
+        write_class_ranges(&mut negated, &complement).unwrap();
+        negated.push(']');
+        if self.is_empty() || negated.len() < plain.len() {
+            negated
+        } else {
+            plain
+        }
+    }
+}
+
+fn parse_class_char(
+    chars: &mut Peekable<CharIndices<'_>>,
+    len: usize,
+) -> Result<char, ClassParseError> {
+    let error = |position, kind| ClassParseError { position, kind };
+    let (position, c) = chars
+        .next()
+        .ok_or(error(len, ClassParseErrorKind::UnclosedClass))?;
+    if c != '\\' {
+        return Ok(c);
+    }
+    let invalid = error(position, ClassParseErrorKind::InvalidEscape);
+    let (_, c) = chars
+        .next()
+        .ok_or(error(len, ClassParseErrorKind::UnclosedClass))?;
+    let digits = match c {
+        'n' => return Ok('\n'),
+        't' => return Ok('\t'),
+        'r' => return Ok('\r'),
+        'f' => return Ok('\u{C}'),
+        'v' => return Ok('\u{B}'),
+        'x' => 2,
+        'u' => 4,
+        c if c.is_ascii_punctuation() => return Ok(c),
+        _ => return Err(invalid),
+    };
+    let mut value = 0u32;
+    let mut read = 0;
+    let braced = chars.next_if(|&(_, c)| c == '{').is_some();
+    loop {
+        if braced && chars.next_if(|&(_, c)| c == '}').is_some() {
+            break;
+        }
+        if !braced && read == digits {
+            break;
+        }
+        let (_, c) = chars.next().ok_or(invalid)?;
+        let digit = c.to_digit(16).ok_or(invalid)?;
+        value = value.checked_mul(16).ok_or(invalid)? + digit;
+        read += 1;
+    }
+    if read == 0 {
+        return Err(invalid);
+    }
+    char::from_u32(value).ok_or(invalid)
+}
+
+fn write_class_ranges(out: &mut String, list: &InversionList<char>) -> fmt::Result {
+    let mut ranges = list.iter().peekable();
+    while let Some(range) = ranges.next() {
+        let start = *range.start();
+        let mut end = *range.end();
+        // print adjacent ranges as one
+        while let Some(next) = ranges.next_if(|next| successor(end) == Some(*next.start())) {
+            end = *next.end();
+        }
+        write_class_char(out, start)?;
+        if start != end {
+            if successor(start) != Some(end) {
+                out.push('-');
+            }
+            write_class_char(out, end)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_class_char(out: &mut String, c: char) -> fmt::Result {
+    match c {
+        '\\' | ']' | '[' | '^' | '-' => write!(out, "\\{}", c),
+        ' '..='~' => out.write_char(c),
+        _ => write!(out, "\\x{{{:X}}}", c as u32),
+    }
+}
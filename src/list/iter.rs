@@ -1,64 +1,91 @@
-use core::iter::{Chain, FusedIterator, IntoIterator};
-use core::ops::Range;
+use core::iter::{FusedIterator, IntoIterator};
+use core::ops::{RangeBounds, RangeInclusive};
 
 use crate::list::InversionList;
+use crate::map::Entry;
+use crate::util::{predecessor, successor};
 use crate::{map, OrderedIndex};
 
 impl<Ty: OrderedIndex> InversionList<Ty> {
     /// An iterator over the inner ranges contained in this list.
-    pub fn iter(&self) -> Iter<Ty> {
+    pub fn iter(&self) -> Iter<'_, Ty> {
         Iter {
             iter: self.0.iter(),
         }
     }
 
-    /// Visits the elements representing the difference, i.e., the elements that are in self but not in other, in ascending order.
+    /// An iterator over every single index covered by this list, in ascending order.
+    pub fn elements(&self) -> Elements<'_, Ty> {
+        Elements {
+            iter: self.0.elements(),
+        }
+    }
+
+    /// An iterator over the ranges overlapping the given range, in ascending order.
+    ///
+    /// The ranges are yielded as is, so the first and last ones may reach past `range`, see
+    /// [`Self::range_clipped`] for a version that cuts them down.
+    pub fn range<R: RangeBounds<Ty>>(&self, range: R) -> Range<'_, Ty> {
+        Range {
+            iter: self.0.range(range),
+        }
+    }
+
+    /// An iterator over the ranges overlapping the given range, in ascending order, with the first
+    /// and last ones clipped to `range`.
+    pub fn range_clipped<R: RangeBounds<Ty>>(&self, range: R) -> Range<'_, Ty> {
+        Range {
+            iter: self.0.range_clipped(range),
+        }
+    }
+
+    /// An iterator over the ranges inside of `bounds` that are not covered by this list, in
+    /// ascending order.
+    pub fn gaps<R: RangeBounds<Ty>>(&self, bounds: R) -> Gaps<'_, Ty> {
+        Gaps {
+            iter: self.0.gaps(bounds),
+        }
+    }
+
+    /// Visits the ranges representing the difference, i.e., the elements that are in self but not in other, in ascending order.
     pub fn difference<'this>(&'this self, other: &'this Self) -> Difference<'this, Ty> {
         Difference {
-            iter: self.into_iter(),
-            other,
+            iter: SetOp::new(self, other, |a, b| a && !b),
         }
     }
 
-    /// Visits the elements representing the symmetric difference, i.e., the elements that are in self or in other but not in both, in ascending order.
+    /// Visits the ranges representing the symmetric difference, i.e., the elements that are in self or in other but not in both, in ascending order.
     pub fn symmetric_difference<'this>(
         &'this self,
         other: &'this Self,
     ) -> SymmetricDifference<'this, Ty> {
         SymmetricDifference {
-            iter: self.difference(other).chain(other.difference(self)),
+            iter: SetOp::new(self, other, |a, b| a != b),
         }
     }
 
-    /// Visits the elements representing the intersection, i.e., the elements that are both in self and other, in ascending order.
+    /// Visits the ranges representing the intersection, i.e., the elements that are both in self and other, in ascending order.
     pub fn intersection<'this>(&'this self, other: &'this Self) -> Intersection<'this, Ty> {
-        let (iter, other) = if self.len() <= other.len() {
-            (self.iter(), other)
-        } else {
-            (other.iter(), self)
-        };
-        Intersection { iter, other }
+        Intersection {
+            iter: SetOp::new(self, other, |a, b| a && b),
+        }
     }
 
-    /// Visits the elements representing the union, i.e., all the elements in self or other, without duplicates, in ascending order.
+    /// Visits the ranges representing the union, i.e., all the elements in self or other, without duplicates, in ascending order.
     pub fn union<'this>(&'this self, other: &'this Self) -> Union<'this, Ty> {
         Union {
-            iter: if self.len() >= other.len() {
-                self.iter().chain(other.difference(self))
-            } else {
-                other.iter().chain(self.difference(other))
-            },
+            iter: SetOp::new(self, other, |a, b| a || b),
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Iter<'a, Ty: OrderedIndex> {
-    iter: map::Iter<'a, Ty, ()>,
+    pub(super) iter: map::Iter<'a, Ty, ()>,
 }
 
 impl<Ty: OrderedIndex> Iterator for Iter<'_, Ty> {
-    type Item = Range<Ty>;
+    type Item = RangeInclusive<Ty>;
     fn next(&mut self) -> Option<Self::Item> {
         self.iter.next().map(|(k, ())| k)
     }
@@ -68,6 +95,13 @@ impl<Ty: OrderedIndex> Iterator for Iter<'_, Ty> {
     }
 }
 
+impl<Ty: OrderedIndex> DoubleEndedIterator for Iter<'_, Ty> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(k, ())| k)
+    }
+}
+
 impl<Ty: OrderedIndex> FusedIterator for Iter<'_, Ty> {}
 impl<Ty: OrderedIndex> ExactSizeIterator for Iter<'_, Ty> {
     #[inline]
@@ -77,20 +111,109 @@ impl<Ty: OrderedIndex> ExactSizeIterator for Iter<'_, Ty> {
 }
 
 impl<'a, Ty: OrderedIndex> IntoIterator for &'a InversionList<Ty> {
-    type Item = Range<Ty>;
+    type Item = RangeInclusive<Ty>;
     type IntoIter = Iter<'a, Ty>;
     fn into_iter(self) -> Self::IntoIter {
         self.iter()
     }
 }
 
+/// An iterator over the individual indices covered by a list, see [`InversionList::elements`].
+#[derive(Debug, Clone)]
+pub struct Elements<'a, Ty: OrderedIndex> {
+    iter: map::Elements<'a, Ty, ()>,
+}
+
+impl<Ty: OrderedIndex> Iterator for Elements<'_, Ty> {
+    type Item = Ty;
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, ())| k)
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Ty: OrderedIndex> DoubleEndedIterator for Elements<'_, Ty> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(k, ())| k)
+    }
+}
+
+impl<Ty: OrderedIndex> FusedIterator for Elements<'_, Ty> {}
+
+/// An iterator over the ranges of a list overlapping a range, see [`InversionList::range`] and
+/// [`InversionList::range_clipped`].
+#[derive(Debug, Clone)]
+pub struct Range<'a, Ty: OrderedIndex> {
+    iter: map::Range<'a, Ty, ()>,
+}
+
+impl<Ty: OrderedIndex> Iterator for Range<'_, Ty> {
+    type Item = RangeInclusive<Ty>;
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, ())| k)
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Ty: OrderedIndex> DoubleEndedIterator for Range<'_, Ty> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(k, ())| k)
+    }
+}
+
+impl<Ty: OrderedIndex> FusedIterator for Range<'_, Ty> {}
+impl<Ty: OrderedIndex> ExactSizeIterator for Range<'_, Ty> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+/// An iterator over the uncovered ranges of a list within some bounds, see
+/// [`InversionList::gaps`].
+#[derive(Debug, Clone)]
+pub struct Gaps<'a, Ty: OrderedIndex> {
+    iter: map::Gaps<'a, Ty, ()>,
+}
+
+impl<Ty: OrderedIndex> Iterator for Gaps<'_, Ty> {
+    type Item = RangeInclusive<Ty>;
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Ty: OrderedIndex> DoubleEndedIterator for Gaps<'_, Ty> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+impl<Ty: OrderedIndex> FusedIterator for Gaps<'_, Ty> {}
+
 #[derive(Debug, Clone)]
 pub struct IntoIter<Ty: OrderedIndex> {
     iter: map::IntoIter<Ty, ()>,
 }
 
 impl<Ty: OrderedIndex> Iterator for IntoIter<Ty> {
-    type Item = Range<Ty>;
+    type Item = RangeInclusive<Ty>;
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
         self.iter.next().map(|(k, ())| k)
@@ -101,6 +224,13 @@ impl<Ty: OrderedIndex> Iterator for IntoIter<Ty> {
     }
 }
 
+impl<Ty: OrderedIndex> DoubleEndedIterator for IntoIter<Ty> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(k, ())| k)
+    }
+}
+
 impl<Ty: OrderedIndex> FusedIterator for IntoIter<Ty> {}
 impl<Ty: OrderedIndex> ExactSizeIterator for IntoIter<Ty> {
     #[inline]
@@ -110,7 +240,7 @@ impl<Ty: OrderedIndex> ExactSizeIterator for IntoIter<Ty> {
 }
 
 impl<Ty: OrderedIndex> IntoIterator for InversionList<Ty> {
-    type Item = Range<Ty>;
+    type Item = RangeInclusive<Ty>;
     type IntoIter = IntoIter<Ty>;
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
@@ -120,29 +250,186 @@ impl<Ty: OrderedIndex> IntoIterator for InversionList<Ty> {
     }
 }
 
+/// Lazily merges the ranges of two lists, yielding the maximal runs of indices for which `op`
+/// holds given the membership of the index in either list.
+///
+/// `op(false, false)` has to be `false`, otherwise the iterator would have to yield the gaps.
 #[derive(Debug, Clone)]
-pub struct Difference<'a, Ty: OrderedIndex> {
-    pub(crate) iter: Iter<'a, Ty>,
-    pub(crate) other: &'a InversionList<Ty>,
+struct SetOp<'a, Ty: OrderedIndex> {
+    a: &'a [Entry<Ty, ()>],
+    b: &'a [Entry<Ty, ()>],
+    // the indices left to look at from either end, `None` once the iterator is exhausted
+    //
+    // Both ends only ever stop right outside of a run, so no run is split between them.
+    bounds: Option<(Ty, Ty)>,
+    op: fn(bool, bool) -> bool,
 }
 
-impl<Ty: OrderedIndex> Iterator for Difference<'_, Ty> {
-    type Item = Range<Ty>;
+impl<'a, Ty: OrderedIndex> SetOp<'a, Ty> {
+    fn new(a: &'a InversionList<Ty>, b: &'a InversionList<Ty>, op: fn(bool, bool) -> bool) -> Self {
+        debug_assert!(!op(false, false));
+        SetOp {
+            a: &a.0.ranges,
+            b: &b.0.ranges,
+            bounds: Some((Ty::min_value(), Ty::max_value())),
+            op,
+        }
+    }
+
+    /// Drops all ranges ending before `pos` and returns whether `pos` lies in the first remaining
+    /// range, as well as the next index at which that changes.
+    fn advance(ranges: &mut &'a [Entry<Ty, ()>], pos: Ty) -> (bool, Option<Ty>) {
+        while let [first, rest @ ..] = *ranges {
+            if first.end >= pos {
+                break;
+            }
+            *ranges = rest;
+        }
+        match ranges.first() {
+            Some(first) if first.start <= pos => (true, successor(first.end)),
+            Some(first) => (false, Some(first.start)),
+            None => (false, None),
+        }
+    }
+
+    /// Drops all ranges starting after `pos` and returns whether `pos` lies in the last remaining
+    /// range, as well as the previous index at which that changes.
+    fn retreat(ranges: &mut &'a [Entry<Ty, ()>], pos: Ty) -> (bool, Option<Ty>) {
+        while let [rest @ .., last] = *ranges {
+            if last.start <= pos {
+                break;
+            }
+            *ranges = rest;
+        }
+        match ranges.last() {
+            Some(last) if pos <= last.end => (true, predecessor(last.start)),
+            Some(last) => (false, Some(last.end)),
+            None => (false, None),
+        }
+    }
+
+    /// Returns whether `op` holds at `pos`, and the next index at which that might change.
+    fn state(&mut self, pos: Ty) -> (bool, Option<Ty>) {
+        let (in_a, next_a) = Self::advance(&mut self.a, pos);
+        let (in_b, next_b) = Self::advance(&mut self.b, pos);
+        let next = match (next_a, next_b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        ((self.op)(in_a, in_b), next)
+    }
+
+    /// Returns whether `op` holds at `pos`, and the previous index at which that might change.
+    fn state_back(&mut self, pos: Ty) -> (bool, Option<Ty>) {
+        let (in_a, prev_a) = Self::retreat(&mut self.a, pos);
+        let (in_b, prev_b) = Self::retreat(&mut self.b, pos);
+        let prev = match (prev_a, prev_b) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        ((self.op)(in_a, in_b), prev)
+    }
+}
+
+impl<Ty: OrderedIndex> Iterator for SetOp<'_, Ty> {
+    type Item = RangeInclusive<Ty>;
 
-    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
+        let (mut start, back) = self.bounds?;
+        loop {
+            match self.state(start) {
+                (true, _) if start <= back => break,
+                (false, Some(next)) if next <= back => start = next,
+                _ => {
+                    self.bounds = None;
+                    return None;
+                }
+            }
+        }
+        let mut pos = start;
+        loop {
+            match self.state(pos) {
+                (true, Some(next)) => pos = next,
+                (true, None) => {
+                    self.bounds = None;
+                    return Some(start..=Ty::max_value());
+                }
+                (false, _) => {
+                    // `start < pos` as `op` held at `start`, so this can't underflow
+                    let end = predecessor(pos).unwrap();
+                    self.bounds = (pos <= back).then_some((pos, back));
+                    return Some(start..=end);
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.bounds {
+            Some(_) => (0, Some(self.a.len() + self.b.len())),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+impl<Ty: OrderedIndex> DoubleEndedIterator for SetOp<'_, Ty> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (front, mut end) = self.bounds?;
         loop {
-            let next = self.iter.next()?;
-            if !self.other.contains_range(next.clone()) {
-                break Some(next);
+            match self.state_back(end) {
+                (true, _) if front <= end => break,
+                (false, Some(prev)) if front <= prev => end = prev,
+                _ => {
+                    self.bounds = None;
+                    return None;
+                }
             }
         }
+        let mut pos = end;
+        loop {
+            match self.state_back(pos) {
+                (true, Some(prev)) => pos = prev,
+                (true, None) => {
+                    self.bounds = None;
+                    return Some(Ty::min_value()..=end);
+                }
+                (false, _) => {
+                    // `pos < end` as `op` held at `end`, so this can't overflow
+                    let start = successor(pos).unwrap();
+                    self.bounds = (front <= pos).then_some((front, pos));
+                    return Some(start..=end);
+                }
+            }
+        }
+    }
+}
+
+impl<Ty: OrderedIndex> FusedIterator for SetOp<'_, Ty> {}
+
+#[derive(Debug, Clone)]
+pub struct Difference<'a, Ty: OrderedIndex> {
+    iter: SetOp<'a, Ty>,
+}
+
+impl<Ty: OrderedIndex> Iterator for Difference<'_, Ty> {
+    type Item = RangeInclusive<Ty>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
     }
 
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let (_, upper) = self.iter.size_hint();
-        (0, upper)
+        self.iter.size_hint()
+    }
+}
+
+impl<Ty: OrderedIndex> DoubleEndedIterator for Difference<'_, Ty> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
     }
 }
 
@@ -150,11 +437,11 @@ impl<Ty: OrderedIndex> FusedIterator for Difference<'_, Ty> {}
 
 #[derive(Debug, Clone)]
 pub struct SymmetricDifference<'a, Ty: OrderedIndex> {
-    pub(crate) iter: Chain<Difference<'a, Ty>, Difference<'a, Ty>>,
+    iter: SetOp<'a, Ty>,
 }
 
 impl<Ty: OrderedIndex> Iterator for SymmetricDifference<'_, Ty> {
-    type Item = Range<Ty>;
+    type Item = RangeInclusive<Ty>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
@@ -167,15 +454,22 @@ impl<Ty: OrderedIndex> Iterator for SymmetricDifference<'_, Ty> {
     }
 }
 
+impl<Ty: OrderedIndex> DoubleEndedIterator for SymmetricDifference<'_, Ty> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
 impl<Ty: OrderedIndex> FusedIterator for SymmetricDifference<'_, Ty> {}
 
 #[derive(Debug, Clone)]
 pub struct Union<'a, Ty: OrderedIndex> {
-    pub(crate) iter: Chain<Iter<'a, Ty>, Difference<'a, Ty>>,
+    iter: SetOp<'a, Ty>,
 }
 
 impl<Ty: OrderedIndex> Iterator for Union<'_, Ty> {
-    type Item = Range<Ty>;
+    type Item = RangeInclusive<Ty>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
@@ -188,31 +482,38 @@ impl<Ty: OrderedIndex> Iterator for Union<'_, Ty> {
     }
 }
 
+impl<Ty: OrderedIndex> DoubleEndedIterator for Union<'_, Ty> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
 impl<Ty: OrderedIndex> FusedIterator for Union<'_, Ty> {}
 
 #[derive(Debug, Clone)]
 pub struct Intersection<'a, Ty: OrderedIndex> {
-    pub(crate) iter: Iter<'a, Ty>,
-    pub(crate) other: &'a InversionList<Ty>,
+    iter: SetOp<'a, Ty>,
 }
 
 impl<Ty: OrderedIndex> Iterator for Intersection<'_, Ty> {
-    type Item = Range<Ty>;
+    type Item = RangeInclusive<Ty>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            let next = self.iter.next()?;
-            if self.other.contains_range(next.clone()) {
-                break Some(next);
-            }
-        }
+        self.iter.next()
     }
 
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let (_, upper) = self.iter.size_hint();
-        (0, upper)
+        self.iter.size_hint()
+    }
+}
+
+impl<Ty: OrderedIndex> DoubleEndedIterator for Intersection<'_, Ty> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
     }
 }
 
@@ -0,0 +1,223 @@
+//! Linear merges over the sorted range vectors of two lists.
+//!
+//! All functions walk both inputs once. Whenever one side runs ahead of the other, the lagging
+//! side is skipped with an exponential search so that combining a small list with a big one only
+//! costs `O(m log(n / m))` comparisons, plus whatever has to be copied into the result.
+
+use core::cmp::Ordering;
+
+use alloc::vec::Vec;
+
+use crate::map::Entry;
+use crate::util::{predecessor, successor};
+use crate::OrderedIndex;
+
+type Ranges<Idx> = [Entry<Idx, ()>];
+
+/// Returns the length of the prefix of `slice` for which `pred` holds, `pred` has to be
+/// monotonically decreasing over the slice.
+///
+/// The search first doubles its step size until it overshoots, and only then bisects, so the cost
+/// is logarithmic in the returned value rather than in the length of the slice.
+fn gallop<T>(slice: &[T], pred: impl Fn(&T) -> bool) -> usize {
+    let mut bound = 1;
+    while bound < slice.len() && pred(&slice[bound]) {
+        bound *= 2;
+    }
+    let lo = bound / 2;
+    let hi = slice.len().min(bound + 1);
+    lo + slice[lo..hi].partition_point(pred)
+}
+
+/// Whether `entry` ends strictly before `idx` without touching it.
+#[inline]
+fn ends_before<Idx: OrderedIndex>(entry: &Entry<Idx, ()>, idx: Idx) -> bool {
+    successor(entry.end).is_some_and(|next| next < idx)
+}
+
+/// Pushes `start..=end` onto `out`, merging it into the last range if they touch.
+#[inline]
+fn push<Idx: OrderedIndex>(out: &mut Vec<Entry<Idx, ()>>, start: Idx, end: Idx) {
+    match out.last_mut() {
+        Some(last) if !ends_before(last, start) => last.end = last.end.max(end),
+        _ => out.push(Entry {
+            start,
+            end,
+            value: (),
+        }),
+    }
+}
+
+/// Pushes all of `ranges` onto `out`, only the first one may get merged with the output.
+#[inline]
+fn extend<Idx: OrderedIndex>(out: &mut Vec<Entry<Idx, ()>>, ranges: &Ranges<Idx>) {
+    if let [first, rest @ ..] = ranges {
+        push(out, first.start, first.end);
+        out.extend_from_slice(rest);
+    }
+}
+
+pub(crate) fn union<Idx: OrderedIndex>(a: &Ranges<Idx>, b: &Ranges<Idx>) -> Vec<Entry<Idx, ()>> {
+    let mut out = Vec::with_capacity(a.len().max(b.len()));
+    let (mut a, mut b) = (a, b);
+    while let ([x, ..], [y, ..]) = (a, b) {
+        if ends_before(x, y.start) {
+            let n = gallop(a, |e| ends_before(e, y.start));
+            extend(&mut out, &a[..n]);
+            a = &a[n..];
+        } else if ends_before(y, x.start) {
+            let n = gallop(b, |e| ends_before(e, x.start));
+            extend(&mut out, &b[..n]);
+            b = &b[n..];
+        } else {
+            // the two ranges touch, so whichever ends first is swallowed by the merged range
+            push(&mut out, x.start.min(y.start), x.end.max(y.end));
+            if x.end <= y.end {
+                a = &a[1..];
+            } else {
+                b = &b[1..];
+            }
+        }
+    }
+    extend(&mut out, a);
+    extend(&mut out, b);
+    out
+}
+
+pub(crate) fn intersection<Idx: OrderedIndex>(
+    a: &Ranges<Idx>,
+    b: &Ranges<Idx>,
+) -> Vec<Entry<Idx, ()>> {
+    let mut out = Vec::new();
+    let (mut a, mut b) = (a, b);
+    while let ([x, ..], [y, ..]) = (a, b) {
+        if x.end < y.start {
+            a = &a[gallop(a, |e| e.end < y.start)..];
+        } else if y.end < x.start {
+            b = &b[gallop(b, |e| e.end < x.start)..];
+        } else {
+            out.push(Entry {
+                start: x.start.max(y.start),
+                end: x.end.min(y.end),
+                value: (),
+            });
+            if x.end <= y.end {
+                a = &a[1..];
+            } else {
+                b = &b[1..];
+            }
+        }
+    }
+    out
+}
+
+pub(crate) fn difference<Idx: OrderedIndex>(
+    a: &Ranges<Idx>,
+    b: &Ranges<Idx>,
+) -> Vec<Entry<Idx, ()>> {
+    let mut out = Vec::with_capacity(a.len());
+    let (mut a, mut b) = (a, b);
+    // the part of `a[0]` that has not been consumed yet
+    let mut start = a.first().map(|x| x.start);
+    while let (Some(x_start), [x, ..], [y, ..]) = (start, a, b) {
+        if x.end < y.start {
+            out.push(Entry {
+                start: x_start,
+                end: x.end,
+                value: (),
+            });
+            // everything up to `y` is unaffected and can be copied over as is
+            let n = 1 + gallop(&a[1..], |e| e.end < y.start);
+            out.extend_from_slice(&a[1..n]);
+            a = &a[n..];
+            start = a.first().map(|x| x.start);
+        } else if y.end < x_start {
+            b = &b[gallop(b, |e| e.end < x_start)..];
+        } else {
+            if x_start < y.start {
+                out.push(Entry {
+                    start: x_start,
+                    // `x_start < y.start` so this can't underflow
+                    end: predecessor(y.start).unwrap(),
+                    value: (),
+                });
+            }
+            if x.end <= y.end {
+                a = &a[1..];
+                start = a.first().map(|x| x.start);
+            } else {
+                // `y.end < x.end` so this can't overflow
+                start = successor(y.end);
+                b = &b[1..];
+            }
+        }
+    }
+    if let (Some(x_start), [x, rest @ ..]) = (start, a) {
+        out.push(Entry {
+            start: x_start,
+            end: x.end,
+            value: (),
+        });
+        out.extend_from_slice(rest);
+    }
+    out
+}
+
+pub(crate) fn symmetric_difference<Idx: OrderedIndex>(
+    a: &Ranges<Idx>,
+    b: &Ranges<Idx>,
+) -> Vec<Entry<Idx, ()>> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut a, mut b) = (a, b);
+    // the not yet consumed parts of `a[0]` and `b[0]`
+    let mut a_start = a.first().map(|x| x.start);
+    let mut b_start = b.first().map(|y| y.start);
+    while let (Some(x_start), Some(y_start), [x, ..], [y, ..]) = (a_start, b_start, a, b) {
+        if x.end < y_start {
+            push(&mut out, x_start, x.end);
+            let n = 1 + gallop(&a[1..], |e| e.end < y_start);
+            extend(&mut out, &a[1..n]);
+            a = &a[n..];
+            a_start = a.first().map(|x| x.start);
+        } else if y.end < x_start {
+            push(&mut out, y_start, y.end);
+            let n = 1 + gallop(&b[1..], |e| e.end < x_start);
+            extend(&mut out, &b[1..n]);
+            b = &b[n..];
+            b_start = b.first().map(|y| y.start);
+        } else {
+            // emit the part in front of the overlap, the overlap itself is dropped
+            match x_start.cmp(&y_start) {
+                Ordering::Less => {
+                    push(&mut out, x_start, predecessor(y_start).unwrap());
+                }
+                Ordering::Greater => {
+                    push(&mut out, y_start, predecessor(x_start).unwrap());
+                }
+                Ordering::Equal => (),
+            }
+            let overlap_end = x.end.min(y.end);
+            if x.end == overlap_end {
+                a = &a[1..];
+                a_start = a.first().map(|x| x.start);
+            } else {
+                a_start = successor(overlap_end);
+            }
+            if y.end == overlap_end {
+                b = &b[1..];
+                b_start = b.first().map(|y| y.start);
+            } else {
+                b_start = successor(overlap_end);
+            }
+        }
+    }
+    if let (Some(x_start), [x, rest @ ..]) = (a_start, a) {
+        push(&mut out, x_start, x.end);
+        out.extend_from_slice(rest);
+    }
+    if let (Some(y_start), [y, rest @ ..]) = (b_start, b) {
+        push(&mut out, y_start, y.end);
+        out.extend_from_slice(rest);
+    }
+    out
+}
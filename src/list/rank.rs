@@ -0,0 +1,14 @@
+use crate::list::InversionList;
+use crate::OrderedIndex;
+
+impl<Idx: OrderedIndex> InversionList<Idx> {
+    /// Returns the number of covered indices smaller than `index` in `O(log n)`.
+    pub fn rank(&self, index: Idx) -> u128 {
+        self.0.rank(index)
+    }
+
+    /// Returns the `n`th covered index in ascending order, counting from zero, in `O(log n)`.
+    pub fn select(&self, n: u128) -> Option<Idx> {
+        self.0.select(n).map(|(index, ())| index)
+    }
+}
@@ -0,0 +1,95 @@
+use core::ops::{RangeBounds, RangeInclusive};
+
+use crate::list::{InversionList, Iter};
+use crate::{InversionMap, OrderedIndex};
+
+/// A non-canonical [`InversionList`] whose ranges can be split into directly adjacent parts.
+///
+/// Split ranges that are right next to each other will not be recognized as one. Meaning
+/// functions like [`Self::contains_range`] will not work properly if the start and end points lie
+/// in different parts of neighbouring ranges, and equality compares the ranges rather than the
+/// covered indices. Converting back into an [`InversionList`] merges the parts again.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SplitInversionList<Idx: OrderedIndex = usize>(InversionMap<Idx, ()>);
+
+impl<Idx: OrderedIndex> SplitInversionList<Idx> {
+    pub fn new() -> Self {
+        SplitInversionList(InversionMap::new())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// An iterator over the ranges contained in this list, split parts are yielded separately.
+    pub fn iter(&self) -> Iter<'_, Idx> {
+        Iter {
+            iter: self.0.iter(),
+        }
+    }
+
+    /// Checks whether the given index is inside any of the contained ranges.
+    pub fn contains(&self, index: Idx) -> bool {
+        self.0.contains(index)
+    }
+
+    /// Checks whether a single range of this list contains the given range.
+    pub fn contains_range<R: RangeBounds<Idx>>(&self, range: R) -> bool {
+        self.0.contains_range(range)
+    }
+
+    /// Looks up the range, or the part of it, the given index is part of.
+    pub fn lookup(&self, index: Idx) -> Option<RangeInclusive<Idx>> {
+        self.0.lookup(index).map(|(range, _)| range)
+    }
+
+    /// Inserts a range without merging it with the ranges directly adjacent to it.
+    pub fn insert_range<R: RangeBounds<Idx>>(&mut self, range: R) {
+        self.0.insert_range_with(range, |_| ());
+    }
+
+    pub fn remove_range<R: RangeBounds<Idx>>(&mut self, range: R) {
+        self.0.remove_range(range, |_, _| (), |_, _| ());
+    }
+
+    /// Splits the range that contains `at` in two with `at` being the split point.
+    ///
+    /// The right range will start at `at`. If `at` is equal to the start of the range it is in,
+    /// no split occurs.
+    pub fn split(&mut self, at: Idx) {
+        self.0.split(at);
+    }
+
+    /// Merges the ranges at `start` and `end`, discarding all ranges inbetween them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the indices dont point to a valid index into the vec.
+    pub fn merge(&mut self, start: usize, end: usize) {
+        self.0.ranges.update(start, |ranges| {
+            ranges[start].end = ranges[end].end;
+            ranges.drain(start + 1..=end);
+        });
+    }
+
+    /// Merges all ranges together that are directly adjacent to each other.
+    pub fn collapse(self) -> InversionList<Idx> {
+        InversionList::from_map(self.0)
+    }
+}
+
+impl<Idx: OrderedIndex> From<InversionList<Idx>> for SplitInversionList<Idx> {
+    fn from(list: InversionList<Idx>) -> Self {
+        SplitInversionList(list.0)
+    }
+}
+
+impl<Idx: OrderedIndex> From<SplitInversionList<Idx>> for InversionList<Idx> {
+    fn from(list: SplitInversionList<Idx>) -> Self {
+        list.collapse()
+    }
+}
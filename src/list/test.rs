@@ -0,0 +1,539 @@
+use super::*;
+use crate::testing::{random_list, xorshift};
+
+macro_rules! il {
+    ($($range:expr),* $(,)?) => {
+        {
+            let mut il = InversionList::new();
+            $(il.insert_range($range);)*
+            il
+        }
+    };
+}
+
+#[test]
+fn is_subset() {
+    let il: InversionList = il![1..10, 15..26, 61..100];
+    let il2 = il![1..5, 17..22, 77..88];
+    let il3 = il![1..10, 77..88];
+    assert!(il.is_subset(&il));
+    assert!(il2.is_subset(&il));
+    assert!(il3.is_subset(&il));
+    assert!(!il.is_subset(&il2));
+    assert!(!il.is_subset(&il3));
+
+    assert!(il.is_superset(&il));
+    assert!(il.is_superset(&il2));
+    assert!(il.is_superset(&il3));
+    assert!(!il2.is_superset(&il));
+    assert!(!il3.is_superset(&il));
+}
+
+#[test]
+fn is_disjoint() {
+    let il: InversionList = il![1..10, 15..26, 61..100];
+    let il2 = il![1..5, 17..22, 77..88, 100..166];
+    let il3 = il![1..10, 37..54, 66..100];
+    let il4 = il![10..15, 44..55, 60..61];
+    assert!(!il.is_disjoint(&il));
+    assert!(!il.is_disjoint(&il2));
+    assert!(!il.is_disjoint(&il3));
+    assert!(il.is_disjoint(&il4));
+}
+
+#[test]
+fn collapse() {
+    let il: InversionList = il![1..10, 10..26, 30..33, 33..35, 35..40, 41..45];
+    assert!(il.iter().eq([1..=25, 30..=39, 41..=44]));
+
+    let mut split = SplitInversionList::from(il.clone());
+    split.split(5);
+    split.split(35);
+    split.insert_range(45..50);
+    assert!(split
+        .iter()
+        .eq([1..=4, 5..=25, 30..=34, 35..=39, 41..=44, 45..=49]));
+    assert!(!split.contains_range(4..6));
+    split.merge(0, 1);
+    assert_eq!(split.lookup(4), Some(1..=25));
+    assert_eq!(split.collapse(), il![1..26, 30..40, 41..50]);
+}
+
+#[test]
+fn invert() {
+    let mut il: InversionList = il![1..10, 10..26, 30..33, 33..35, 35..40, 41..45];
+    il.invert();
+    assert_eq!(il, il![0..1, 26..30, 40..41, 45..=usize::MAX]);
+    let mut il: InversionList = il![0..10, 15..26, 26..33, 34..35, 35..36];
+    il.invert();
+    assert_eq!(il, il![10..15, 33..34, 36..=usize::MAX]);
+}
+
+#[test]
+fn test_not() {
+    let il: InversionList = il![0..5, 5..15, 20..25, 50..80];
+    assert_eq!(!il, il![15..20, 25..50, 80..=usize::MAX]);
+    let il: InversionList = il![5..15, 20..25, 50..80];
+    assert_eq!(!il, il![0..5, 15..20, 25..50, 80..=usize::MAX]);
+}
+
+#[test]
+fn not_full_domain() {
+    let full: InversionList<u8> = il![..=u8::MAX];
+    assert_eq!(full.span(), Some(0..=255));
+    assert!(full.contains(255));
+    assert_eq!(!&full, InversionList::new());
+    assert_eq!(!InversionList::<u8>::new(), full);
+    assert_eq!(!!full.clone(), full);
+
+    let il: InversionList<u8> = il![0..10, 250..=255];
+    assert_eq!(!&il, il![10..250]);
+    assert_eq!(il.lookup(255), Some(250..=255));
+    assert_eq!(il.iter().collect::<Vec<_>>(), [0..=9, 250..=255]);
+}
+
+#[test]
+fn contains_range() {
+    let il: InversionList<u8> = il![1..10, 15..=255];
+    assert!(il.contains_range(2..5));
+    assert!(il.contains_range(20..=255));
+    assert!(!il.contains_range(5..20));
+    assert!(!il.contains_range(10..12));
+}
+
+#[test]
+fn bitand() {
+    let il: InversionList<u8> = il![0..10, 20..30, 200..=255];
+    let il2 = il![5..25, 250..=255];
+    assert_eq!(&il & &il2, il![5..10, 20..25, 250..=255]);
+}
+
+#[test]
+fn char_skips_surrogates() {
+    use crate::OrderedIndex;
+    assert_eq!('\u{D7FF}'.checked_add(char::one()), Some('\u{E000}'));
+    assert_eq!('\u{E000}'.checked_sub(char::one()), Some('\u{D7FF}'));
+    assert_eq!(char::MAX.checked_add(char::one()), None);
+    assert_eq!('\0'.checked_sub(char::one()), None);
+
+    let il: InversionList<char> = il!['\u{D000}'..='\u{D7FF}', '\u{E000}'..='\u{E010}'];
+    assert_eq!(il, il!['\u{D000}'..='\u{E010}']);
+    assert_eq!(!!il.clone(), il);
+    assert_eq!(!InversionList::<char>::new(), il![..]);
+}
+
+#[test]
+fn from_chars() {
+    let il = InversionList::from_chars("hello world");
+    assert_eq!(
+        il,
+        il![
+            ' '..=' ',
+            'd'..='e',
+            'h'..='h',
+            'l'..='l',
+            'o'..='o',
+            'r'..='r',
+            'w'..='w'
+        ]
+    );
+    assert!(il.contains_all_chars("hello"));
+    assert!(il.contains_all_chars(""));
+    assert!(!il.contains_all_chars("help"));
+
+    let il = InversionList::from_chars("cbadcba");
+    assert_eq!(il, il!['a'..='d']);
+}
+
+#[test]
+fn from_regex_class() {
+    let il = InversionList::from_regex_class(r"[a-zA-Z_\u{80}-\u{10FFFF}]").unwrap();
+    assert_eq!(
+        il,
+        il!['A'..='Z', '_'..='_', 'a'..='z', '\u{80}'..='\u{10FFFF}']
+    );
+    assert_eq!(
+        InversionList::from_regex_class(r"[^\x00-\x{2F}\u003A-\u{10FFFF}]").unwrap(),
+        il!['0'..='9']
+    );
+    assert_eq!(
+        InversionList::from_regex_class(r"[]\-\\^-]").unwrap(),
+        InversionList::from_chars("]-\\^")
+    );
+    assert_eq!(
+        InversionList::from_regex_class(r"[\n\t -]").unwrap(),
+        InversionList::from_chars("\n\t -")
+    );
+
+    let err = |s| InversionList::from_regex_class(s).unwrap_err();
+    let kind = |s| err(s).kind;
+    assert_eq!(kind("a-z]"), ClassParseErrorKind::ExpectedOpenBracket);
+    assert_eq!(kind("[a-z"), ClassParseErrorKind::UnclosedClass);
+    assert_eq!(kind("[]"), ClassParseErrorKind::UnclosedClass);
+    assert_eq!(err(r"[a\d]").position, 2);
+    assert_eq!(kind(r"[\d]"), ClassParseErrorKind::InvalidEscape);
+    assert_eq!(kind(r"[\u{D800}]"), ClassParseErrorKind::InvalidEscape);
+    assert_eq!(kind(r"[\x4]"), ClassParseErrorKind::InvalidEscape);
+    assert_eq!(err("[ab-a]").position, 2);
+    assert_eq!(kind("[ab-a]"), ClassParseErrorKind::ReversedRange);
+    assert_eq!(err("[a]b").position, 3);
+}
+
+#[test]
+fn to_regex_class() {
+    let class = |il: InversionList<char>| il.to_regex_class();
+    assert_eq!(class(il!['a'..='c', 'x'..='y']), "[a-cxy]");
+    assert_eq!(
+        class(InversionList::from_chars("-]^\\\n")),
+        r"[\x{A}\-\\-\^]"
+    );
+    assert_eq!(class(InversionList::from_chars("&~[")), r"[&\[~]");
+    assert_eq!(class(il!['\u{80}'..='\u{FF}']), r"[\x{80}-\x{FF}]");
+    assert_eq!(class(il!['\u{80}'..]), r"[^\x{0}-\x{7F}]");
+    assert_eq!(class(!il!['a'..='a']), "[^a]");
+    assert_eq!(class(InversionList::new()), r"[^\x{0}-\x{10FFFF}]");
+    assert_eq!(class(il![..]), r"[\x{0}-\x{10FFFF}]");
+
+    let mut seed = 0x1234_5678;
+    for _ in 0..200 {
+        let (il, _) = random_list(&mut seed);
+        let il: InversionList<char> = il
+            .iter()
+            .map(|r| char::from(*r.start())..=char::from(*r.end()))
+            .collect();
+        let class = il.to_regex_class();
+        assert_eq!(InversionList::from_regex_class(&class), Ok(il), "{}", class);
+    }
+}
+
+fn to_bits(il: &InversionList<u8>) -> [bool; 256] {
+    let mut bits = [false; 256];
+    for range in il {
+        bits[*range.start() as usize..=*range.end() as usize].fill(true);
+    }
+    bits
+}
+
+/// Checks that no two ranges touch or overlap.
+fn is_canonical(il: &InversionList<u8>) -> bool {
+    il.0.ranges
+        .windows(2)
+        .all(|w| w[0].end < w[1].start && w[1].start - w[0].end > 1)
+}
+
+#[test]
+fn matches_bitset() {
+    let mut seed = 0x2545_f491;
+    let count = |bits: [bool; 256]| bits.iter().filter(|&&b| b).count() as u128;
+    for _ in 0..500 {
+        let (a, a_bits) = random_list(&mut seed);
+        let (b, b_bits) = random_list(&mut seed);
+
+        // set operations and their counts
+        let check = |res: InversionList<u8>, op: fn(bool, bool) -> bool| {
+            let expected: [bool; 256] = core::array::from_fn(|i| op(a_bits[i], b_bits[i]));
+            assert_eq!(to_bits(&res), expected, "{a:?} {b:?} => {res:?}");
+            assert!(is_canonical(&res), "{a:?} {b:?} => {res:?}");
+            count(expected)
+        };
+        assert_eq!(a.union_count(&b), check(&a | &b, |a, b| a | b));
+        assert_eq!(a.intersection_count(&b), check(&a & &b, |a, b| a & b));
+        assert_eq!(a.difference_count(&b), check(&a - &b, |a, b| a & !b));
+        check(&a ^ &b, |a, b| a ^ b);
+        assert_eq!(a.count(), count(a_bits));
+
+        // elements, rank and select
+        let covered: Vec<u8> = (0..=255).filter(|&i| a_bits[usize::from(i)]).collect();
+        assert!(a.elements().eq(covered.iter().copied()));
+        assert!(a.elements().rev().eq(covered.iter().rev().copied()));
+        for v in 0..=255u8 {
+            let below = covered.partition_point(|&c| c < v) as u128;
+            assert_eq!(a.rank(v), below);
+        }
+        for (n, &c) in covered.iter().enumerate() {
+            assert_eq!(a.select(n as u128), Some(c));
+        }
+        assert_eq!(a.select(covered.len() as u128), None);
+
+        // gaps within random bounds
+        let (lo, hi) = (
+            (xorshift(&mut seed) % 256) as u8,
+            (xorshift(&mut seed) % 256) as u8,
+        );
+        let (lo, hi) = (lo.min(hi), lo.max(hi));
+        let expected: InversionList<u8> = (lo..=hi)
+            .filter(|&i| !a_bits[usize::from(i)])
+            .map(|i| i..=i)
+            .collect();
+        assert!(a.gaps(lo..=hi).eq(expected.iter()), "{a:?} {lo}..={hi}");
+        assert!(a.gaps(lo..=hi).rev().eq(expected.iter().rev()));
+        assert_eq!(a.complement_within(lo..=hi), expected);
+        assert_eq!(a.complement_within(..), !&a);
+    }
+}
+
+#[test]
+fn set_ops_gallop() {
+    let big: InversionList<u32> = (0..10_000).map(|i| i * 10..i * 10 + 5).collect();
+    let small: InversionList<u32> = il![52..58, 50_000..50_003, 99_990..=u32::MAX];
+    assert_eq!(&big & &small, il![52..55, 50_000..50_003, 99_990..99_995]);
+    assert_eq!(&small & &big, il![52..55, 50_000..50_003, 99_990..99_995]);
+    assert_eq!((&big | &small).len(), big.len());
+    assert_eq!(&small - &big, il![55..58, 99_995..=u32::MAX]);
+    assert_eq!((&big - &small).len(), big.len() - 1);
+    assert_eq!((&big ^ &small) & il![40..60], il![40..45, 50..52, 55..58]);
+
+    let mut il = big.clone();
+    il -= &big;
+    assert!(il.is_empty());
+    il |= small.clone();
+    il ^= small;
+    assert!(il.is_empty());
+}
+
+#[test]
+fn lazy_set_ops() {
+    let a: InversionList = il![0..10];
+    let b = il![3..5];
+    assert_eq!(a.difference(&b).collect::<Vec<_>>(), [0..=2, 5..=9]);
+    assert_eq!(b.difference(&a).count(), 0);
+
+    let a: InversionList = il![0..5, 5..10, 20..30];
+    let b = il![8..22];
+    assert_eq!(a.intersection(&b).collect::<Vec<_>>(), [8..=9, 20..=21]);
+    assert_eq!(a.union(&b).collect::<Vec<_>>(), [0..=29]);
+    assert_eq!(
+        a.symmetric_difference(&b).collect::<Vec<_>>(),
+        [0..=7, 10..=19, 22..=29]
+    );
+
+    let a: InversionList<u8> = il![250..=255];
+    let b = il![0..=1];
+    assert_eq!(a.union(&b).collect::<Vec<_>>(), [0..=1, 250..=255]);
+    assert_eq!(a.difference(&b).collect::<Vec<_>>(), [250..=255]);
+}
+
+#[test]
+fn lazy_set_ops_match_operators() {
+    let mut seed = 0x1234_5678;
+    for _ in 0..500 {
+        let (a, _) = random_list(&mut seed);
+        let (b, _) = random_list(&mut seed);
+        assert_eq!(a.union(&b).collect::<InversionList<_>>(), &a | &b);
+        assert_eq!(a.intersection(&b).collect::<InversionList<_>>(), &a & &b);
+        assert_eq!(a.difference(&b).collect::<InversionList<_>>(), &a - &b);
+        assert_eq!(
+            a.symmetric_difference(&b).collect::<InversionList<_>>(),
+            &a ^ &b
+        );
+    }
+}
+
+#[test]
+fn count() {
+    assert_eq!(il![0..10, 20..=20].count(), 11);
+    assert_eq!(InversionList::<u8>::new().count(), 0);
+    assert_eq!(il![i8::MIN..].count(), 256);
+    assert_eq!((!InversionList::<char>::new()).count(), 0x11_0000 - 0x800);
+    assert_eq!(il![u128::MIN..u128::MAX].count(), u128::MAX);
+    assert_eq!(il![i128::MIN..].count(), u128::MAX);
+
+    assert_eq!(OrderedIndex::steps_between(-3i8, 5), Some(8));
+    assert_eq!(
+        OrderedIndex::steps_between(i128::MIN, i128::MAX),
+        Some(u128::MAX)
+    );
+    assert_eq!(OrderedIndex::steps_between(5u32, 3), None);
+    assert_eq!(OrderedIndex::steps_between('\u{D7FF}', '\u{E000}'), Some(1));
+}
+
+#[test]
+fn select_wide_types() {
+    let il = il![i8::MIN..];
+    assert_eq!(il.select(255), Some(i8::MAX));
+    assert_eq!(il.rank(0), 128);
+    let il = il!['\u{D7FE}'..='\u{E001}'];
+    assert_eq!(il.select(2), Some('\u{E000}'));
+    assert_eq!(il.rank('\u{E001}'), 3);
+    let il = il![0..10, u64::MAX - 4..];
+    assert_eq!(il.select(14), Some(u64::MAX));
+    assert_eq!(il.rank(u64::MAX), 14);
+    // the last index of a full 128-bit domain is its `u128::MAX`th
+    let il = il![u128::MIN..];
+    assert_eq!(il.select(u128::MAX), Some(u128::MAX));
+    assert_eq!(il.select(0), Some(0));
+    assert_eq!(il.rank(u128::MAX), u128::MAX);
+    assert_eq!(il.count(), u128::MAX);
+    let il = il![i128::MIN..];
+    assert_eq!(il.select(u128::MAX), Some(i128::MAX));
+    assert_eq!(il.select(1 << 127), Some(0));
+    let il = il![0..10u128, u128::MAX - 4..];
+    assert_eq!(il.select(14), Some(u128::MAX));
+    assert_eq!(il.select(15), None);
+}
+
+#[test]
+fn elements() {
+    let il: InversionList<u8> = il![0..3, 5..=5, 254..];
+    assert!(il.elements().eq([0, 1, 2, 5, 254, 255]));
+    assert!(il.elements().rev().eq([255, 254, 5, 2, 1, 0]));
+    assert_eq!(il.elements().size_hint(), (6, Some(6)));
+
+    let mut it = il.elements();
+    assert_eq!((it.next(), it.next_back()), (Some(0), Some(255)));
+    assert_eq!((it.next_back(), it.next()), (Some(254), Some(1)));
+    assert_eq!(it.size_hint(), (2, Some(2)));
+    assert_eq!(
+        (it.next(), it.next_back(), it.next()),
+        (Some(2), Some(5), None)
+    );
+    assert_eq!(it.next_back(), None);
+
+    let il: InversionList<u8> = il![7..=7];
+    let mut it = il.elements();
+    assert_eq!((it.next_back(), it.next()), (Some(7), None));
+    assert!(InversionList::<u8>::new().elements().next().is_none());
+    let full: InversionList<u128> = il![..];
+    assert_eq!(full.elements().size_hint(), (usize::MAX, None));
+    assert_eq!(full.elements().next_back(), Some(u128::MAX));
+    let chars = il!['\u{D7FE}'..='\u{E001}'];
+    assert!(chars
+        .elements()
+        .eq(['\u{D7FE}', '\u{D7FF}', '\u{E000}', '\u{E001}']));
+}
+
+#[test]
+fn set_ops_double_ended() {
+    let mut seed = 0x1234_5678;
+    for _ in 0..300 {
+        let (a, _) = random_list(&mut seed);
+        let (b, _) = random_list(&mut seed);
+        let check = |iter: &mut dyn DoubleEndedIterator<Item = RangeInclusive<u8>>,
+                     forward: Vec<RangeInclusive<u8>>| {
+            // alternate between both ends, the runs have to meet up without overlap
+            let (mut front, mut back) = (Vec::new(), Vec::new());
+            while let Some(range) = iter.next() {
+                front.push(range);
+                match iter.next_back() {
+                    Some(range) => back.push(range),
+                    None => break,
+                }
+            }
+            assert_eq!(iter.next(), None);
+            front.extend(back.into_iter().rev());
+            assert_eq!(front, forward, "{a:?} {b:?}");
+        };
+        check(&mut a.union(&b), a.union(&b).collect());
+        check(&mut a.intersection(&b), a.intersection(&b).collect());
+        check(&mut a.difference(&b), a.difference(&b).collect());
+        check(
+            &mut a.symmetric_difference(&b),
+            a.symmetric_difference(&b).collect(),
+        );
+        assert!(a
+            .union(&b)
+            .rev()
+            .eq(a.union(&b).collect::<Vec<_>>().into_iter().rev()));
+        assert!(a.symmetric_difference(&b).rev().eq(a
+            .symmetric_difference(&b)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()));
+    }
+}
+
+#[test]
+fn range() {
+    let il: InversionList<u8> = il![0..10, 20..30, 40..50, 250..];
+    assert!(il.iter().rev().eq([250..=255, 40..=49, 20..=29, 0..=9]));
+    assert!(il.clone().into_iter().rev().eq(il.iter().rev()));
+
+    assert!(il.range(25..45).eq([20..=29, 40..=49]));
+    assert!(il.range_clipped(25..45).eq([25..=29, 40..=44]));
+    assert!(il.range_clipped(25..45).rev().eq([40..=44, 25..=29]));
+    assert!(il.range(10..20).eq([]));
+    assert!(il.range(9..=20).eq([0..=9, 20..=29]));
+    assert!(il.range_clipped(9..=20).eq([9..=9, 20..=20]));
+    assert!(il.range_clipped(252..).eq([252..=255]));
+    assert!(il.range_clipped(..).eq(il.iter()));
+    assert_eq!(il.range(5..).len(), 4);
+    #[allow(clippy::reversed_empty_ranges)]
+    let empty = il.range(30..5);
+    assert!(empty.eq([]));
+}
+
+#[test]
+fn canonical() {
+    let mut il: InversionList<u8> = il![0..5, 5..10];
+    assert_eq!(il, il![0..10]);
+    il.insert_unit(10);
+    il.add_unit(12);
+    il.add_range(13..20);
+    il.insert_range(11..=11);
+    assert!(il.iter().eq([0..=19]));
+    il.remove_range(5..6);
+    il.insert_range(5..=5);
+    assert!(il.iter().eq([0..=19]));
+
+    let mut seed = 0xdead_beef;
+    for _ in 0..200 {
+        let (a, _) = random_list(&mut seed);
+        let (b, _) = random_list(&mut seed);
+        let mut il = a.clone();
+        for range in &b {
+            il.insert_range(range);
+        }
+        assert!(is_canonical(&il), "{il:?}");
+        assert_eq!(il, &a | &b);
+    }
+}
+
+#[test]
+fn ord_and_hash_compare_sets() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let hash = |il: &InversionList<u8>| {
+        let mut hasher = DefaultHasher::new();
+        il.hash(&mut hasher);
+        hasher.finish()
+    };
+    let mut split = SplitInversionList::from(il![0..10, 20..30]);
+    split.split(5);
+    let il = InversionList::from(split);
+    assert_eq!(hash(&il), hash(&il![0..10, 20..30]));
+
+    let mut seed = 0x0bad_cafe;
+    for _ in 0..500 {
+        let (a, a_bits) = random_list(&mut seed);
+        let (b, b_bits) = random_list(&mut seed);
+        let elements = |bits: [bool; 256]| (0..=255u8).filter(move |&i| bits[usize::from(i)]);
+        assert_eq!(
+            a.cmp(&b),
+            elements(a_bits).cmp(elements(b_bits)),
+            "{a:?} {b:?}"
+        );
+        assert_eq!(a == b, a_bits == b_bits);
+    }
+    assert!(il![0..5] < il![0..5, 7..8]);
+    assert!(il![0..5, 7..8] > il![0..6]);
+    assert!(il![1..2] > il![0..100]);
+    assert!(InversionList::<u8>::new() < il![0..1]);
+}
+
+#[test]
+fn gaps() {
+    let il: InversionList<u8> = il![10..20, 30..40, 250..];
+    assert!(il.gaps(..).eq([0..=9, 20..=29, 40..=249]));
+    assert!(il.gaps(15..35).eq([20..=29]));
+    assert!(il.gaps(5..=12).eq([5..=9]));
+    assert!(il.gaps(12..18).eq([]));
+    assert!(il.gaps(255..).eq([]));
+    assert!(il.gaps(35..).rev().eq([40..=249]));
+    assert!(InversionList::<u8>::new().gaps(3..7).eq([3..=6]));
+    assert_eq!(il.complement_within(0..35), il![0..10, 20..30]);
+
+    let mut it = il.gaps(..);
+    assert_eq!((it.next_back(), it.next()), (Some(40..=249), Some(0..=9)));
+    assert_eq!((it.next_back(), it.next()), (Some(20..=29), None));
+}
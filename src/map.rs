@@ -1,35 +1,29 @@
-use core::cmp::Ordering;
-use core::iter::once;
+use core::ops;
 use core::ops::Not;
-use core::ops::{Range, RangeBounds};
-use core::{mem, ops};
+use core::ops::{RangeBounds, RangeInclusive};
 
 use alloc::vec::Vec;
 
-use crate::util::bounds_to_range;
+use crate::raw;
 use crate::util::variance::CovariantLifetime;
-use crate::OrderedIndex;
+use crate::util::{bounds_to_range, predecessor, successor};
+use crate::{InversionList, OrderedIndex};
 
-use Err as Insert;
-use Ok as Within;
+pub use crate::raw::EntriesRef;
+pub(crate) use crate::raw::Entry;
 
 #[cfg(test)]
 mod test;
 
+mod coalescing;
+mod cursor;
 mod iter;
-pub use self::iter::{IntoIter, Iter};
-
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub(crate) struct Entry<Idx, V> {
-    pub(crate) range: Range<Idx>,
-    pub(crate) value: V,
-}
-
-impl<Idx, V> From<Entry<Idx, V>> for (Range<Idx>, V) {
-    fn from(Entry { range, value }: Entry<Idx, V>) -> Self {
-        (range, value)
-    }
-}
+mod merge;
+mod rank;
+pub use self::coalescing::CoalescingMap;
+pub use self::cursor::{Cursor, CursorMut, InsertError};
+pub use self::iter::{Elements, Gaps, IntoIter, Iter, IterMut, Range, ValuesMut};
+pub(crate) use self::rank::Ranges;
 
 // These are public APIs that abstract away the internal representation of the inversion map.
 
@@ -48,44 +42,55 @@ impl<'im, Idx, V> Entries<'im, Idx, V> {
     }
 }
 
-pub struct EntriesRef<'im, Idx, V> {
-    slice: &'im [Entry<Idx, V>],
+/// Mutable access to the values of a run of entries, see [`InversionMap::lookup_range_mut`].
+///
+/// The ranges stay read-only so that the ordering of the map can't be broken.
+pub struct EntriesMut<'im, Idx, V> {
+    slice: &'im mut [Entry<Idx, V>],
 }
 
-impl<'im, Idx: OrderedIndex, V> EntriesRef<'im, Idx, V> {
-    pub const fn is_empty(&self) -> bool {
+impl<'im, Idx: OrderedIndex, V> EntriesMut<'im, Idx, V> {
+    pub fn is_empty(&self) -> bool {
         self.slice.is_empty()
     }
 
-    pub const fn len(&self) -> usize {
+    pub fn len(&self) -> usize {
         self.slice.len()
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = (Range<Idx>, &V)> + '_ {
-        self.slice
-            .iter()
-            .map(|Entry { range, value }| (range.clone(), value))
+    pub fn iter(&self) -> Iter<'_, Idx, V> {
+        Iter::new(self.slice)
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, Idx, V> {
+        IterMut::new(self.slice)
     }
 }
 
-pub struct EntriesMut<'im, Idx, V> {
-    it: &'im mut [Entry<Idx, V>],
+impl<'im, Idx: OrderedIndex, V> IntoIterator for EntriesMut<'im, Idx, V> {
+    type Item = (RangeInclusive<Idx>, &'im mut V);
+    type IntoIter = IterMut<'im, Idx, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        IterMut::new(self.slice)
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
 pub struct InversionMap<Idx, V> {
     // FIXME: use MaybeUninit so we can prevent some frequent clones
-    pub(crate) ranges: Vec<Entry<Idx, V>>,
+    pub(crate) ranges: Ranges<Idx, V>,
 }
 
 impl<Idx, V> InversionMap<Idx, V> {
     pub fn new() -> Self {
-        InversionMap { ranges: Vec::new() }
+        InversionMap {
+            ranges: Ranges::new(),
+        }
     }
 
     pub fn with_capacity(capacity: usize) -> Self {
         InversionMap {
-            ranges: Vec::with_capacity(capacity),
+            ranges: Ranges::with_capacity(capacity),
         }
     }
 }
@@ -99,7 +104,7 @@ impl<Idx: OrderedIndex, V> InversionMap<Idx, V> {
 
     #[inline]
     pub fn clear(&mut self) {
-        self.ranges.clear();
+        self.ranges.update(0, Vec::clear);
     }
 
     #[inline]
@@ -114,22 +119,23 @@ impl<Idx: OrderedIndex, V> InversionMap<Idx, V> {
 
     #[inline]
     pub fn start(&self) -> Option<Idx> {
-        self.ranges.first().map(|r| r.range.start)
+        self.ranges.first().map(|r| r.start)
     }
 
+    /// Returns the last index covered by the map, this is an inclusive bound.
     #[inline]
     pub fn end(&self) -> Option<Idx> {
-        self.ranges.last().map(|r| r.range.end)
+        self.ranges.last().map(|r| r.end)
     }
 
     #[inline]
-    pub fn first(&self) -> Option<(Range<Idx>, &V)> {
-        self.ranges.first().map(|r| (r.range.clone(), &r.value))
+    pub fn first(&self) -> Option<(RangeInclusive<Idx>, &V)> {
+        self.ranges.first().map(|r| (r.range(), &r.value))
     }
 
     #[inline]
-    pub fn last(&self) -> Option<(Range<Idx>, &V)> {
-        self.ranges.last().map(|r| (r.range.clone(), &r.value))
+    pub fn last(&self) -> Option<(RangeInclusive<Idx>, &V)> {
+        self.ranges.last().map(|r| (r.range(), &r.value))
     }
 }
 // endregion
@@ -140,67 +146,100 @@ impl<Idx: OrderedIndex, V> InversionMap<Idx, V> {
         self.binary_search(index).is_ok()
     }
 
+    /// Checks whether a single range of the map fully contains the given range.
+    ///
+    /// Empty ranges are never contained.
+    pub fn contains_range<R: RangeBounds<Idx>>(&self, range: R) -> bool {
+        bounds_to_range(range).is_some_and(|range| raw::contains_range(&self.ranges, range))
+    }
+
     /// Looks up the range and value associated with the given index.
-    pub fn lookup(&self, index: Idx) -> Option<(Range<Idx>, &V)> {
+    pub fn lookup(&self, index: Idx) -> Option<(RangeInclusive<Idx>, &V)> {
         self.binary_search(index)
-            .map(|idx| (self.ranges[idx].range.clone(), &self.ranges[idx].value))
+            .map(|idx| (self.ranges[idx].range(), &self.ranges[idx].value))
             .ok()
     }
 
     /// Looks up all entries whose ranges overlap with the given range.
     pub fn lookup_range<R: RangeBounds<Idx>>(&self, range: R) -> Option<EntriesRef<'_, Idx, V>> {
         let range = bounds_to_range(range)?;
-        let slice = match self.range_binary_search(range) {
-            (Within(s), Within(e)) => &self.ranges[s..=e],
-            (Insert(s) | Within(s), Insert(e)) => &self.ranges[s..e],
-            (Insert(s), Within(e)) => &self.ranges[s + 1..=e],
-        };
-        slice.is_empty().not().then(|| EntriesRef { slice })
+        let slice = &self.ranges[self.overlapping(range)];
+        slice.is_empty().not().then_some(EntriesRef { slice })
+    }
+
+    /// Returns a mutable reference to the value associated with the given index.
+    pub fn get_mut(&mut self, index: Idx) -> Option<&mut V> {
+        self.lookup_mut(index).map(|(_, value)| value)
+    }
+
+    /// Looks up the range and a mutable reference to the value associated with the given index.
+    pub fn lookup_mut(&mut self, index: Idx) -> Option<(RangeInclusive<Idx>, &mut V)> {
+        let idx = self.binary_search(index).ok()?;
+        let entry = &mut self.ranges.values_mut()[idx];
+        Some((entry.range(), &mut entry.value))
+    }
+
+    /// Looks up all entries whose ranges overlap with the given range, granting mutable access to
+    /// their values.
+    pub fn lookup_range_mut<R: RangeBounds<Idx>>(
+        &mut self,
+        range: R,
+    ) -> Option<EntriesMut<'_, Idx, V>> {
+        let range = bounds_to_range(range)?;
+        let overlapping = self.overlapping(range);
+        let slice = &mut self.ranges.values_mut()[overlapping];
+        slice.is_empty().not().then_some(EntriesMut { slice })
     }
 
     /// Check if the given range intersects with any ranges inside of the inversion list.
     pub fn intersects<R: RangeBounds<Idx>>(&self, range: R) -> bool {
-        let Some(range) = bounds_to_range(range) else {
-            // empty range can't intersect
-            return false;
-        };
-        match self.range_binary_search(range) {
-            // check if there is at least one range inside of our range
-            (Insert(idx_s), Insert(idx_e)) => idx_e - idx_s > 0,
-            _ => true,
-        }
+        // empty ranges can't intersect
+        bounds_to_range(range).is_some_and(|range| raw::intersects(&self.ranges, range))
     }
 
     /// Returns the complete surrounding range, if any.
-    pub fn span(&self) -> Option<Range<Idx>> {
+    pub fn span(&self) -> Option<RangeInclusive<Idx>> {
         let start = self.start()?;
         let end = self.end()?;
-        Some(start..end)
+        Some(start..=end)
+    }
+
+    /// Returns the number of indices covered by the map, saturating at `u128::MAX`.
+    pub fn count(&self) -> u128 {
+        self.ranges.count()
+    }
+
+    /// Returns the indices inside of `bounds` that are not covered by this map.
+    pub fn complement_within<R: RangeBounds<Idx>>(&self, bounds: R) -> InversionList<Idx> {
+        // gaps are separated by entries, so they are never adjacent
+        InversionList(InversionMap {
+            ranges: self
+                .gaps(bounds)
+                .map(|range| Entry {
+                    start: *range.start(),
+                    end: *range.end(),
+                    value: (),
+                })
+                .collect(),
+        })
     }
 }
 
 impl<Idx: OrderedIndex, V: Clone> InversionMap<Idx, V> {
+    /// Inserts a unit range(index..=index) into the map, overwriting the value at `index`.
+    ///
+    /// If the unit is not part of an existing range, `true` is returned.
+    ///
+    /// If the unit already exists in a range, `false` is returned and the range will be split
+    /// around `index`.
     pub fn insert_unit(&mut self, index: Idx, value: V) -> bool {
-        match self.binary_search(index) {
-            Insert(insert_idx) => {
-                self.ranges.insert(
-                    insert_idx,
-                    Entry {
-                        range: index
-                            ..index
-                                .checked_add(Idx::one())
-                                .expect("index is equal to usize::MAX"),
-                        value,
-                    },
-                );
-                true
-            }
-            Within(idx) => {
-                let (_, r) = self.split_impl(idx, index, |_, v| (v.clone(), v));
-                self.split_impl(r, index, |_, v| (value, v));
-                false
-            }
-        }
+        let from = self.ranges.affected_from(index);
+        let Ok(inserted) = self.ranges.update(from, |ranges| {
+            raw::unit_with(ranges, index, value, |ranges, _, value| {
+                raw::insert_range_with(ranges, index..=index, |_| value)
+            })
+        });
+        inserted
     }
 
     /// Inserts a new range with a given value into the map overwriting any ranges that are contained within.
@@ -212,115 +251,37 @@ impl<Idx: OrderedIndex, V: Clone> InversionMap<Idx, V> {
     /// Inserts a new range with a value produced by `value` into the map. `value` gets passed all
     /// overlapping entries. If start or end overlap with a range, the overlapping range will be
     /// split accordingly.
-    pub fn insert_range_with<'im, R: RangeBounds<Idx>>(
-        &'im mut self,
+    pub fn insert_range_with<R: RangeBounds<Idx>>(
+        &mut self,
         range: R,
         value: impl FnOnce(EntriesRef<'_, Idx, V>) -> V,
     ) {
-        let Some(range) = bounds_to_range(range) else {
-            return;
-        };
-        match self.range_binary_search(range.clone()) {
-            (Within(idx_s), Insert(idx_e)) => {
-                let slice = &self.ranges[idx_s..idx_e];
-                let value = value(EntriesRef { slice });
-                match slice {
-                    [] => unreachable!("range was empty and should've been filtered out"),
-                    [_] => {
-                        self.ranges[idx_s].range.end = range.start;
-                        self.ranges.insert(idx_e, Entry { range, value });
-                    }
-                    [_, .., _] => {
-                        self.ranges[idx_s].range.end = range.start;
-                        self.ranges
-                            .splice(idx_s + 1..idx_e, once(Entry { range, value }));
-                    }
-                }
-            }
-            (Insert(idx_s), Insert(idx_e)) => {
-                let slice = &self.ranges[idx_s..idx_e];
-                let value = value(EntriesRef { slice });
-                match slice {
-                    [] => self.ranges.insert(idx_s, Entry { range, value }),
-                    [..] => {
-                        self.ranges
-                            .splice(idx_s..idx_e, once(Entry { range, value }));
-                    }
-                }
-            }
-            (Insert(idx_s), Within(idx_e)) => {
-                let slice = &self.ranges[idx_s..=idx_e];
-                let value = value(EntriesRef { slice });
-                match slice {
-                    [] => unreachable!(),
-                    [.., _] => {
-                        self.ranges[idx_e].range.start = range.end;
-                        self.ranges
-                            .splice(idx_s..idx_e, once(Entry { range, value }));
-                    }
-                }
-            }
-            (Within(idx_s), Within(idx_e)) => {
-                let slice = &self.ranges[idx_s..=idx_e];
-                let value = value(EntriesRef { slice });
-                match slice {
-                    [] => unreachable!(),
-                    [entry] => {
-                        let end_val_clone = entry.value.clone();
-                        let end_end = mem::replace(&mut self.ranges[idx_s].range.end, range.start);
-                        self.ranges.insert(
-                            idx_s + 1,
-                            Entry {
-                                range: range.end..end_end,
-                                value: end_val_clone,
-                            },
-                        );
-                        self.ranges.insert(idx_s + 1, Entry { range, value });
-                    }
-                    [_, .., _] => {
-                        self.ranges[idx_s].range.end = range.start;
-                        self.ranges[idx_e].range.start = range.end;
-                        self.ranges
-                            .splice(idx_s + 1..idx_e, once(Entry { range, value }));
-                    }
-                }
-            }
-        };
+        if let Some(range) = bounds_to_range(range) {
+            let from = self.ranges.affected_from(*range.start());
+            let Ok(()) = self
+                .ranges
+                .update(from, |ranges| raw::insert_range_with(ranges, range, value));
+        }
     }
 }
 
 impl<Idx: OrderedIndex, V> InversionMap<Idx, V> {
-    /// Adds a unit range(index..index + 1) to the inversion list. This is faster than using
+    /// Adds a unit range(index..=index) to the inversion list. This is faster than using
     /// [`Self::add_range`] saving a second binary_search.
     ///
     /// If the unit is not part of an existing range, `true` is returned.
     ///
     /// If the unit already exists in a range, `false` is returned and the range value will be set
     /// to `value`.
-    ///
-    /// # Panics
-    ///
-    /// Panics if index is equal to [`OrderedIndex::max_value()`].
     pub fn add_unit(&mut self, index: Idx, value: V) -> bool {
-        match self.binary_search(index) {
-            Insert(insert_idx) => {
-                self.ranges.insert(
-                    insert_idx,
-                    Entry {
-                        range: index
-                            ..index
-                                .checked_add(Idx::one())
-                                .expect("index is equal to usize::MAX"),
-                        value,
-                    },
-                );
-                true
-            }
-            Within(idx) => {
-                self.ranges[idx].value = value;
-                false
-            }
-        }
+        let from = self.ranges.affected_from(index);
+        let Ok(inserted) = self.ranges.update(from, |ranges| {
+            raw::unit_with(ranges, index, value, |ranges, idx, value| {
+                ranges[idx].value = value;
+                Ok(())
+            })
+        });
+        inserted
     }
 
     pub fn add_range<R: RangeBounds<Idx>>(&mut self, range: R, value: V) {
@@ -335,79 +296,12 @@ impl<Idx: OrderedIndex, V> InversionMap<Idx, V> {
         range: R,
         value: impl FnOnce(EntriesRef<'_, Idx, V>) -> V,
     ) {
-        let Some(range) = bounds_to_range(range) else {
-            return;
-        };
-
-        match self.range_binary_search(range.clone()) {
-            #[cfg(debug_assertions)]
-            (Within(idx_s), Insert(idx_e)) if idx_s == idx_e => {
-                unreachable!("range was empty and should've been filtered out")
-            }
-            (Within(idx_s) | Insert(idx_s), Insert(idx_e)) => {
-                let slice = &self.ranges[idx_s..idx_e];
-                let value = value(EntriesRef { slice });
-                match slice {
-                    // Same indices, surround nothing so insert
-                    [] => self.ranges.insert(idx_s, Entry { range, value }),
-                    // Surrounding a single element, so replace it
-                    [it] => {
-                        let start = it.range.start.min(range.start);
-                        let end = it.range.end.max(range.end);
-                        self.ranges[idx_s] = Entry {
-                            range: start..end,
-                            value,
-                        };
-                    }
-                    // Surrounding multiple elements, merge them and replace
-                    [start, .., end] => {
-                        let start = start.range.start.min(range.start);
-                        let end = end.range.end.max(range.end);
-                        self.ranges.splice(
-                            idx_s..idx_e,
-                            once(Entry {
-                                range: start..end,
-                                value,
-                            }),
-                        );
-                    }
-                }
-            }
-            (Within(idx_s), Within(idx_e)) => {
-                let slice = &self.ranges[idx_s..=idx_e];
-                let value = value(EntriesRef { slice });
-
-                match slice {
-                    [] => unreachable!(),
-                    [_] => {
-                        self.ranges[idx_s].value = value;
-                    }
-                    [start, .., end] => {
-                        let mut entry = Entry { range, value };
-                        entry.range.start = start.range.start.min(entry.range.start);
-                        entry.range.end = end.range.end.max(entry.range.end);
-                        self.ranges.splice(idx_s..=idx_e, once(entry));
-                    }
-                }
-            }
-            (Insert(idx_s), Within(idx_e)) => {
-                let slice = &self.ranges[idx_s..=idx_e];
-                let value = value(EntriesRef { slice });
-                match slice {
-                    [] => unreachable!(),
-                    [_] => {
-                        self.ranges[idx_e].range.start = range.start.clone();
-                        self.ranges[idx_s].value = value;
-                    }
-                    [start, .., end] => {
-                        let mut entry = Entry { range, value };
-                        entry.range.start = start.range.start.min(entry.range.start);
-                        entry.range.end = end.range.end.max(entry.range.end);
-                        self.ranges.splice(idx_s..=idx_e, once(entry));
-                    }
-                }
-            }
-        };
+        if let Some(range) = bounds_to_range(range) {
+            let from = self.ranges.affected_from(*range.start());
+            let Ok(()) = self
+                .ranges
+                .update(from, |ranges| raw::add_range_with(ranges, range, value));
+        }
     }
 }
 
@@ -416,87 +310,21 @@ impl<Idx: OrderedIndex, V: Clone> InversionMap<Idx, V> {
     pub fn remove_range<R: RangeBounds<Idx>>(
         &mut self,
         range: R,
-        split_boundary_left: impl FnOnce(Range<Idx>, &V) -> V,
-        split_boundary_right: impl FnOnce(Range<Idx>, &V) -> V,
+        split_boundary_left: impl FnOnce(RangeInclusive<Idx>, &V) -> V,
+        split_boundary_right: impl FnOnce(RangeInclusive<Idx>, &V) -> V,
     ) {
-        let Some(range) = bounds_to_range(range) else {
-            return;
-        };
-        match self.range_binary_search(range.clone()) {
-            #[cfg(debug_assertions)]
-            (Within(idx_s), Insert(idx_e)) if idx_s == idx_e => {
-                unreachable!("range was empty and should've been filtered out")
-            }
-            (Insert(idx_s), Insert(idx_e)) => {
-                _ = self.ranges.drain(idx_s..idx_e);
-            }
-            (Within(idx_s), Within(idx_e)) => {
-                let slice = &self.ranges[idx_s..=idx_e];
-                match slice {
-                    [] => unreachable!(),
-                    [entry] => {
-                        let left_range = entry.range.start..range.start;
-                        let left = left_range.is_empty().not().then(|| Entry {
-                            range: left_range,
-                            value: split_boundary_left(entry.range.clone(), &entry.value),
-                        });
-                        let right_range = range.end..entry.range.end;
-                        let right = right_range.is_empty().not().then(|| Entry {
-                            range: right_range,
-                            value: split_boundary_right(entry.range.clone(), &entry.value),
-                        });
-                        self.ranges
-                            .splice(idx_s..=idx_e, [left, right].into_iter().flatten());
-                    }
-                    [start, .., end] => {
-                        let left_range = start.range.start..range.start;
-                        let left = left_range.is_empty().not().then(|| Entry {
-                            range: left_range,
-                            value: split_boundary_left(start.range.clone(), &start.value),
-                        });
-                        let right_range = range.end..end.range.end;
-                        let right = right_range.is_empty().not().then(|| Entry {
-                            range: right_range,
-                            value: split_boundary_right(end.range.clone(), &end.value),
-                        });
-                        self.ranges
-                            .splice(idx_s..=idx_e, [left, right].into_iter().flatten());
-                    }
-                }
-            }
-            (Insert(idx_s), Within(idx_e)) => {
-                let slice = &self.ranges[idx_s..=idx_e];
-                match slice {
-                    [] => unreachable!(),
-                    [.., end] if end.range.end != range.end => {
-                        let right = split_boundary_right(end.range.clone(), &end.value);
-                        self.ranges[idx_e].range.start = range.end;
-                        self.ranges[idx_e].value = right;
-                        self.ranges.drain(idx_s..idx_e);
-                    }
-                    [..] => _ = self.ranges.drain(idx_s..=idx_e),
-                }
-            }
-            (Within(idx_s), Insert(idx_e)) => {
-                let slice = &self.ranges[idx_s..idx_e];
-                match slice {
-                    [] => unreachable!(),
-                    [start, ..] if start.range.start != range.start => {
-                        let left = split_boundary_left(start.range.clone(), &start.value);
-                        self.ranges[idx_s].range.end = range.start;
-                        self.ranges[idx_s].value = left;
-                        self.ranges.drain(idx_s + 1..idx_e);
-                    }
-                    [..] => _ = self.ranges.drain(idx_s..idx_e),
-                }
-            }
-        };
+        if let Some(range) = bounds_to_range(range) {
+            let from = self.ranges.affected_from(*range.start());
+            let Ok(()) = self.ranges.update(from, |ranges| {
+                raw::remove_range(ranges, range, split_boundary_left, split_boundary_right)
+            });
+        }
     }
 
     /// Splits the range that contains `at` in two with `at` being the split point.
     ///
     /// If a range exists that contains `at` the return value are the indices of the
-    /// new left and right ranges of the split point. The left range will contain `at`.
+    /// new left and right ranges of the split point. The right range will start at `at`.
     /// If `at` is equal to the start of the range it is in, no split occurs and the left
     /// and right indices will be equal to the index of the range containing the value.
     ///
@@ -505,94 +333,96 @@ impl<Idx: OrderedIndex, V: Clone> InversionMap<Idx, V> {
     /// points lie in the different parts of the neighbouring ranges. Thus it is important to
     /// either remove these ranges or remerge them.
     pub fn split(&mut self, at: Idx) -> Option<(usize, usize)> {
-        self.binary_search(at)
-            .ok()
-            .map(|idx| self.split_impl(idx, at, |_, v| (v.clone(), v)))
+        self.split_with(at, |_, v| (v.clone(), v))
     }
 
-    /// Like [`split`] but allows for the split to be done with a custom function.
+    /// Like [`Self::split`] but allows for the split to be done with a custom function.
     pub fn split_with(
         &mut self,
         at: Idx,
-        splitter: impl FnOnce(Range<Idx>, V) -> (V, V),
+        splitter: impl FnOnce(RangeInclusive<Idx>, V) -> (V, V),
     ) -> Option<(usize, usize)> {
-        self.binary_search(at)
-            .ok()
-            .map(|idx| self.split_impl(idx, at, splitter))
+        let from = self.ranges.affected_from(at);
+        let Ok(split) = self
+            .ranges
+            .update(from, |ranges| raw::split_with(ranges, at, splitter));
+        split
     }
+}
 
-    // invariant, `at` is inside the range addressed by idx
-    // return value is left range and right range indices of the split range.
-    // The indices are the same if the split point was at the start of the range.
-    fn split_impl(
-        &mut self,
-        idx: usize,
-        at: Idx,
-        splitter: impl FnOnce(Range<Idx>, V) -> (V, V),
-    ) -> (usize, usize) {
-        debug_assert!(self.ranges[idx].range.contains(&at));
-        let to_split = &mut self.ranges[idx];
-        if to_split.range.start != at {
-            let end = mem::replace(&mut to_split.range.end, at);
-            // FIXME: The clone should not be necessary here
-            let value = to_split.value.clone();
-            let (left, right) = splitter(to_split.range.clone(), value);
-            to_split.value = left;
-            self.ranges.insert(
-                idx + 1,
-                Entry {
-                    range: at..end,
-                    value: right,
-                },
-            );
-            (idx, idx + 1)
-        } else {
-            (idx, idx)
-        }
+impl<Idx: OrderedIndex, V> InversionMap<Idx, V> {
+    /// Merges directly adjacent entries whenever `merge` produces a value for the pair of their
+    /// values, the left one being passed first.
+    pub fn coalesce_by(&mut self, mut merge: impl FnMut(&V, &V) -> Option<V>) {
+        self.ranges.update(0, |ranges| {
+            ranges.dedup_by(|right, left| {
+                if successor(left.end) != Some(right.start) {
+                    return false;
+                }
+                match merge(&left.value, &right.value) {
+                    Some(value) => {
+                        left.end = right.end;
+                        left.value = value;
+                        true
+                    }
+                    None => false,
+                }
+            })
+        });
     }
 }
 
-impl<Idx: OrderedIndex, V> InversionMap<Idx, V> {
-    fn bin_search_ordering(left: Ordering, right: Ordering) -> Ordering {
-        use Ordering::*;
-        match (left, right) {
-            // start > key
-            (Greater, _) => Greater,
-            // start == key
-            (Equal, _) => Equal,
-            // start < key && key < end
-            (Less, Less) => Equal,
-            // start < key && key >= end
-            (Less, _) => Less,
+impl<Idx: OrderedIndex, V: PartialEq> InversionMap<Idx, V> {
+    /// Merges all directly adjacent entries that have equal values.
+    pub fn coalesce(&mut self) {
+        self.ranges.update(0, |ranges| {
+            ranges.dedup_by(|right, left| {
+                let merge = successor(left.end) == Some(right.start) && left.value == right.value;
+                if merge {
+                    left.end = right.end;
+                }
+                merge
+            })
+        });
+    }
+
+    /// Merges the directly adjacent entries with equal values among those overlapping or touching
+    /// `range`.
+    pub(crate) fn coalesce_around(&mut self, range: RangeInclusive<Idx>) {
+        let (mut start, mut end) = range.into_inner();
+        // the entries at either end may reach past `range`, so their neighbours need a look too
+        if let Ok(idx) = self.binary_search(start) {
+            start = self.ranges[idx].start;
         }
+        if let Ok(idx) = self.binary_search(end) {
+            end = self.ranges[idx].end;
+        }
+        let widened = predecessor(start).unwrap_or(start)..=successor(end).unwrap_or(end);
+        let overlapping = self.overlapping(widened);
+        self.ranges.update(overlapping.start, |ranges| {
+            let (mut i, mut end) = (overlapping.start + 1, overlapping.end);
+            while i < end {
+                let (left, right) = (&ranges[i - 1], &ranges[i]);
+                if successor(left.end) == Some(right.start) && left.value == right.value {
+                    ranges[i - 1].end = right.end;
+                    ranges.remove(i);
+                    end -= 1;
+                } else {
+                    i += 1;
+                }
+            }
+        });
     }
+}
 
+impl<Idx: OrderedIndex, V> InversionMap<Idx, V> {
     pub(crate) fn binary_search(&self, key: Idx) -> Result<usize, usize> {
-        self.ranges.binary_search_by(move |Entry { range, .. }| {
-            Self::bin_search_ordering(range.start.cmp(&key), key.cmp(&range.end))
-        })
+        raw::binary_search(&self.ranges, key)
     }
 
-    pub(crate) fn range_binary_search(
-        &self,
-        Range { start, end }: Range<Idx>,
-    ) -> (Result<usize, usize>, Result<usize, usize>) {
-        let start @ (Within(idx) | Insert(idx)) = self.binary_search(start);
-        let Some(end) = end.checked_sub(Idx::one()) else {
-            debug_assert!(false);
-            return (Insert(0), Insert(0));
-        };
-        let end = self.ranges[idx..].binary_search_by(move |Entry { range, .. }| {
-            Self::bin_search_ordering(range.start.cmp(&end), end.cmp(&range.end))
-        });
-
-        (
-            start,
-            match end {
-                Within(e) => Within(idx + e),
-                Insert(e) => Insert(idx + e),
-            },
-        )
+    /// Returns the indices of all entries overlapping the given range.
+    pub(crate) fn overlapping(&self, range: RangeInclusive<Idx>) -> core::ops::Range<usize> {
+        raw::overlapping(&self.ranges, range)
     }
 }
 
@@ -611,24 +441,13 @@ where
         };
 
         for (range, value) in iter {
-            let (start, end) = base.range_binary_search(range.clone());
-            let start = start.unwrap_or_else(core::convert::identity);
-            let end = end.unwrap_or_else(core::convert::identity);
-            debug_assert!(start <= end);
-            let base_entry = &base.ranges[start];
-            res.add_range(
-                range.start.max(base_entry.range.start)..range.end.min(base_entry.range.end),
-                (&base_entry.value) & value,
-            );
-            for entry in base.ranges.get((start + 1)..end).into_iter().flatten() {
-                // could just copy slices here for efficiency
-                res.add_range(entry.range.clone(), (&entry.value) & value);
+            let (start, end) = range.clone().into_inner();
+            for entry in &base.ranges[base.overlapping(range)] {
+                res.add_range(
+                    start.max(entry.start)..=end.min(entry.end),
+                    (&entry.value) & value,
+                );
             }
-            let base_entry = &base.ranges[end];
-            res.add_range(
-                range.start.max(base_entry.range.start)..range.end.min(base_entry.range.end),
-                (&base_entry.value) & value,
-            );
         }
 
         res
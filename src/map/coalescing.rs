@@ -0,0 +1,127 @@
+use core::ops::{Deref, RangeBounds, RangeInclusive};
+
+use crate::map::{EntriesRef, InversionMap};
+use crate::util::bounds_to_range;
+use crate::OrderedIndex;
+
+/// An [`InversionMap`] that keeps directly adjacent entries with equal values merged across all
+/// of its mutations.
+///
+/// Two coalescing maps are equal exactly if they map the same indices to the same values. All read
+/// only operations are available through [`Deref`].
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct CoalescingMap<Idx, V> {
+    map: InversionMap<Idx, V>,
+}
+
+impl<Idx, V> CoalescingMap<Idx, V> {
+    pub fn new() -> Self {
+        CoalescingMap {
+            map: InversionMap::new(),
+        }
+    }
+
+    pub fn into_map(self) -> InversionMap<Idx, V> {
+        self.map
+    }
+}
+
+impl<Idx, V> Deref for CoalescingMap<Idx, V> {
+    type Target = InversionMap<Idx, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl<Idx: OrderedIndex, V: PartialEq> From<InversionMap<Idx, V>> for CoalescingMap<Idx, V> {
+    fn from(mut map: InversionMap<Idx, V>) -> Self {
+        map.coalesce();
+        CoalescingMap { map }
+    }
+}
+
+impl<Idx, V> From<CoalescingMap<Idx, V>> for InversionMap<Idx, V> {
+    fn from(map: CoalescingMap<Idx, V>) -> Self {
+        map.map
+    }
+}
+
+impl<Idx: OrderedIndex, V: PartialEq> CoalescingMap<Idx, V> {
+    #[inline]
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// Adds a unit range(index..=index) to the map, see [`InversionMap::add_unit`].
+    pub fn add_unit(&mut self, index: Idx, value: V) -> bool {
+        let inserted = self.map.add_unit(index, value);
+        self.map.coalesce_around(index..=index);
+        inserted
+    }
+
+    /// Adds a new range with a given value to the map, see [`InversionMap::add_range`].
+    pub fn add_range<R: RangeBounds<Idx>>(&mut self, range: R, value: V) {
+        self.add_range_with(range, |_| value);
+    }
+
+    /// Adds a new range with a value produced by `value` to the map, see
+    /// [`InversionMap::add_range_with`].
+    pub fn add_range_with<R: RangeBounds<Idx>>(
+        &mut self,
+        range: R,
+        value: impl FnOnce(EntriesRef<'_, Idx, V>) -> V,
+    ) {
+        if let Some(range) = bounds_to_range(range) {
+            self.map.add_range_with(range.clone(), value);
+            self.map.coalesce_around(range);
+        }
+    }
+}
+
+impl<Idx: OrderedIndex, V: PartialEq + Clone> CoalescingMap<Idx, V> {
+    /// Inserts a unit range(index..=index) into the map, see [`InversionMap::insert_unit`].
+    pub fn insert_unit(&mut self, index: Idx, value: V) -> bool {
+        let inserted = self.map.insert_unit(index, value);
+        self.map.coalesce_around(index..=index);
+        inserted
+    }
+
+    /// Inserts a new range with a given value into the map, see [`InversionMap::insert_range`].
+    pub fn insert_range<R: RangeBounds<Idx>>(&mut self, range: R, value: V) {
+        self.insert_range_with(range, |_| value.clone());
+    }
+
+    /// Inserts a new range with a value produced by `value` into the map, see
+    /// [`InversionMap::insert_range_with`].
+    pub fn insert_range_with<R: RangeBounds<Idx>>(
+        &mut self,
+        range: R,
+        value: impl FnOnce(EntriesRef<'_, Idx, V>) -> V,
+    ) {
+        if let Some(range) = bounds_to_range(range) {
+            self.map.insert_range_with(range.clone(), value);
+            self.map.coalesce_around(range);
+        }
+    }
+
+    /// Removes the range of values overlapping the given range, the entries cut at its boundaries
+    /// keep their values.
+    pub fn remove_range<R: RangeBounds<Idx>>(&mut self, range: R) {
+        // removing can't make two entries adjacent that weren't before
+        self.map
+            .remove_range(range, |_, v| v.clone(), |_, v| v.clone());
+    }
+}
+
+impl<Idx: OrderedIndex, V: PartialEq + Clone> FromIterator<(RangeInclusive<Idx>, V)>
+    for CoalescingMap<Idx, V>
+{
+    fn from_iter<T: IntoIterator<Item = (RangeInclusive<Idx>, V)>>(iter: T) -> Self {
+        let mut res = CoalescingMap::new();
+        for (range, value) in iter {
+            res.insert_range(range, value);
+        }
+        res
+    }
+}
@@ -0,0 +1,319 @@
+use core::fmt;
+use core::ops::{RangeBounds, RangeInclusive};
+
+use crate::map::{Entry, InversionMap};
+use crate::raw;
+use crate::util::bounds_to_range;
+use crate::OrderedIndex;
+
+impl<Idx: OrderedIndex, V> InversionMap<Idx, V> {
+    /// Returns a cursor pointing at the first entry, or the ghost position if the map is empty.
+    pub fn cursor(&self) -> Cursor<'_, Idx, V> {
+        Cursor {
+            entries: &self.ranges,
+            index: 0,
+        }
+    }
+
+    /// Returns a mutable cursor pointing at the first entry, or the ghost position if the map is
+    /// empty.
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, Idx, V> {
+        CursorMut {
+            map: self,
+            index: 0,
+        }
+    }
+
+    /// Returns a cursor pointing at the entry containing `index`, or the first entry after it if
+    /// there is none.
+    pub fn cursor_at(&self, index: Idx) -> Cursor<'_, Idx, V> {
+        Cursor {
+            entries: &self.ranges,
+            index: position(&self.ranges, index),
+        }
+    }
+
+    /// Returns a mutable cursor pointing at the entry containing `index`, or the first entry after
+    /// it if there is none.
+    pub fn cursor_mut_at(&mut self, index: Idx) -> CursorMut<'_, Idx, V> {
+        let index = position(&self.ranges, index);
+        CursorMut { map: self, index }
+    }
+}
+
+/// Returns the position of the entry containing `key`, or the first one after it.
+fn position<Idx: OrderedIndex, V>(entries: &[Entry<Idx, V>], key: Idx) -> usize {
+    match raw::binary_search(entries, key) {
+        Ok(idx) | Err(idx) => idx,
+    }
+}
+
+/// Returns the position `pos` moved by one in either direction, treating `len` as a ghost
+/// position between the last and the first entry.
+fn step(pos: usize, len: usize, forward: bool) -> usize {
+    match (forward, pos) {
+        (true, pos) if pos >= len => 0,
+        (true, pos) => pos + 1,
+        (false, 0) => len,
+        (false, pos) => pos - 1,
+    }
+}
+
+fn entry_at<Idx: OrderedIndex, V>(
+    entries: &[Entry<Idx, V>],
+    pos: usize,
+) -> Option<(RangeInclusive<Idx>, &V)> {
+    entries.get(pos).map(|entry| (entry.range(), &entry.value))
+}
+
+/// A cursor over the entries of a map, see [`InversionMap::cursor`] and
+/// [`InversionMap::cursor_at`].
+///
+/// Besides pointing at an entry, a cursor can point at a "ghost" position between the last and the
+/// first entry, so moving past either end of the map wraps around through it.
+#[derive(Debug)]
+pub struct Cursor<'im, Idx, V> {
+    entries: &'im [Entry<Idx, V>],
+    // `entries.len()` is the ghost position
+    index: usize,
+}
+
+impl<Idx, V> Clone for Cursor<'_, Idx, V> {
+    fn clone(&self) -> Self {
+        Cursor { ..*self }
+    }
+}
+
+impl<'im, Idx: OrderedIndex, V> Cursor<'im, Idx, V> {
+    /// Returns the position of the current entry in the map, or `None` at the ghost position.
+    pub fn index(&self) -> Option<usize> {
+        (self.index < self.entries.len()).then_some(self.index)
+    }
+
+    /// Returns the entry the cursor points at.
+    pub fn current(&self) -> Option<(RangeInclusive<Idx>, &'im V)> {
+        entry_at(self.entries, self.index)
+    }
+
+    /// Returns the entry after the current one without moving the cursor.
+    pub fn peek_next(&self) -> Option<(RangeInclusive<Idx>, &'im V)> {
+        entry_at(self.entries, step(self.index, self.entries.len(), true))
+    }
+
+    /// Returns the entry before the current one without moving the cursor.
+    pub fn peek_prev(&self) -> Option<(RangeInclusive<Idx>, &'im V)> {
+        entry_at(self.entries, step(self.index, self.entries.len(), false))
+    }
+
+    /// Moves the cursor to the next entry.
+    pub fn move_next(&mut self) {
+        self.index = step(self.index, self.entries.len(), true);
+    }
+
+    /// Moves the cursor to the previous entry.
+    pub fn move_prev(&mut self) {
+        self.index = step(self.index, self.entries.len(), false);
+    }
+
+    /// Moves the cursor to the entry containing `index`, or the first entry after it if there is
+    /// none.
+    pub fn seek(&mut self, index: Idx) {
+        self.index = position(self.entries, index);
+    }
+}
+
+/// A cursor over the entries of a map that can edit the map around its position, see
+/// [`InversionMap::cursor_mut`] and [`InversionMap::cursor_mut_at`].
+///
+/// Like [`Cursor`], it can point at a ghost position between the last and the first entry.
+#[derive(Debug)]
+pub struct CursorMut<'im, Idx, V> {
+    map: &'im mut InversionMap<Idx, V>,
+    // `map.len()` is the ghost position
+    index: usize,
+}
+
+impl<Idx: OrderedIndex, V> CursorMut<'_, Idx, V> {
+    /// Returns a read-only cursor pointing at the same position.
+    pub fn as_cursor(&self) -> Cursor<'_, Idx, V> {
+        Cursor {
+            entries: &self.map.ranges,
+            index: self.index,
+        }
+    }
+
+    /// Returns the position of the current entry in the map, or `None` at the ghost position.
+    pub fn index(&self) -> Option<usize> {
+        (self.index < self.map.len()).then_some(self.index)
+    }
+
+    /// Returns the entry the cursor points at.
+    pub fn current(&self) -> Option<(RangeInclusive<Idx>, &V)> {
+        entry_at(&self.map.ranges, self.index)
+    }
+
+    /// Returns the entry the cursor points at with a mutable reference to its value.
+    pub fn current_mut(&mut self) -> Option<(RangeInclusive<Idx>, &mut V)> {
+        self.map
+            .ranges
+            .values_mut()
+            .get_mut(self.index)
+            .map(|entry| (entry.range(), &mut entry.value))
+    }
+
+    /// Returns the entry after the current one without moving the cursor.
+    pub fn peek_next(&self) -> Option<(RangeInclusive<Idx>, &V)> {
+        entry_at(&self.map.ranges, step(self.index, self.map.len(), true))
+    }
+
+    /// Returns the entry before the current one without moving the cursor.
+    pub fn peek_prev(&self) -> Option<(RangeInclusive<Idx>, &V)> {
+        entry_at(&self.map.ranges, step(self.index, self.map.len(), false))
+    }
+
+    /// Moves the cursor to the next entry.
+    pub fn move_next(&mut self) {
+        self.index = step(self.index, self.map.len(), true);
+    }
+
+    /// Moves the cursor to the previous entry.
+    pub fn move_prev(&mut self) {
+        self.index = step(self.index, self.map.len(), false);
+    }
+
+    /// Moves the cursor to the entry containing `index`, or the first entry after it if there is
+    /// none.
+    pub fn seek(&mut self, index: Idx) {
+        self.index = position(&self.map.ranges, index);
+    }
+
+    /// Replaces the value of the current entry, returning the old one.
+    ///
+    /// At the ghost position nothing is replaced and `None` is returned.
+    pub fn set_value(&mut self, value: V) -> Option<V> {
+        let (_, current) = self.current_mut()?;
+        Some(core::mem::replace(current, value))
+    }
+
+    /// Inserts a new entry right before the current one, or at the end of the map at the ghost
+    /// position. The cursor keeps pointing at the same entry.
+    ///
+    /// Fails if the range overlaps the current or the previous entry. Empty ranges are ignored.
+    pub fn insert_before<R: RangeBounds<Idx>>(
+        &mut self,
+        range: R,
+        value: V,
+    ) -> Result<(), InsertError<V>> {
+        let Some(range) = bounds_to_range(range) else {
+            return Ok(());
+        };
+        let at = self.index;
+        self.insert(at, range, value)?;
+        self.index += 1;
+        Ok(())
+    }
+
+    /// Inserts a new entry right after the current one, or at the start of the map at the ghost
+    /// position. The cursor keeps pointing at the same entry.
+    ///
+    /// Fails if the range overlaps the current or the next entry. Empty ranges are ignored.
+    pub fn insert_after<R: RangeBounds<Idx>>(
+        &mut self,
+        range: R,
+        value: V,
+    ) -> Result<(), InsertError<V>> {
+        let Some(range) = bounds_to_range(range) else {
+            return Ok(());
+        };
+        if self.index < self.map.len() {
+            self.insert(self.index + 1, range, value)
+        } else {
+            self.insert(0, range, value)?;
+            self.index += 1;
+            Ok(())
+        }
+    }
+
+    /// Inserts the entry at `pos` if it fits in between its neighbours.
+    fn insert(
+        &mut self,
+        pos: usize,
+        range: RangeInclusive<Idx>,
+        value: V,
+    ) -> Result<(), InsertError<V>> {
+        let entries = &self.map.ranges;
+        let (start, end) = range.into_inner();
+        let fits_prev = pos == 0 || entries[pos - 1].end < start;
+        let fits_next = entries.get(pos).is_none_or(|next| end < next.start);
+        if !(fits_prev && fits_next) {
+            return Err(InsertError { value });
+        }
+        self.map.ranges.update(pos, |ranges| {
+            ranges.insert(pos, Entry { start, end, value })
+        });
+        Ok(())
+    }
+
+    /// Removes the current entry and returns it, moving the cursor to the next entry.
+    ///
+    /// At the ghost position nothing is removed and `None` is returned.
+    pub fn remove_current(&mut self) -> Option<(RangeInclusive<Idx>, V)> {
+        let index = self.index;
+        (index < self.map.len()).then(|| {
+            let entry = self.map.ranges.update(index, |ranges| ranges.remove(index));
+            entry.into()
+        })
+    }
+}
+
+impl<Idx: OrderedIndex, V: Clone> CursorMut<'_, Idx, V> {
+    /// Splits the current entry in two with the right part starting at `at`, cloning the value.
+    /// The cursor keeps pointing at the left part.
+    ///
+    /// Returns whether a split occurred, which is not the case if `at` lies outside of the current
+    /// entry or is its start.
+    pub fn split_current(&mut self, at: Idx) -> bool {
+        self.split_current_with(at, |_, v| (v.clone(), v))
+    }
+
+    /// Like [`Self::split_current`] but allows for the split to be done with a custom function.
+    pub fn split_current_with(
+        &mut self,
+        at: Idx,
+        splitter: impl FnOnce(RangeInclusive<Idx>, V) -> (V, V),
+    ) -> bool {
+        match self.map.ranges.get(self.index) {
+            Some(entry) if entry.start < at && at <= entry.end => {
+                let index = self.index;
+                let Ok(_) = self
+                    .map
+                    .ranges
+                    .update(index, |ranges| raw::split_impl(ranges, index, at, splitter));
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The error returned when a [`CursorMut`] insertion would overlap a neighbouring entry, carrying
+/// the value that was to be inserted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InsertError<V> {
+    value: V,
+}
+
+impl<V> InsertError<V> {
+    /// Returns the value that could not be inserted.
+    pub fn into_value(self) -> V {
+        self.value
+    }
+}
+
+impl<V> fmt::Display for InsertError<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("range overlaps a neighbouring entry")
+    }
+}
+
+impl<V: fmt::Debug> core::error::Error for InsertError<V> {}
@@ -1,37 +1,119 @@
 use core::iter::{FusedIterator, IntoIterator};
-use core::ops::Range;
+use core::ops::{RangeBounds, RangeInclusive};
+use core::slice;
 
 use crate::map::{Entry, InversionMap};
+use crate::raw;
+pub use crate::raw::Iter;
+use crate::util::{bounds_to_range, predecessor, successor};
 use crate::OrderedIndex;
 
 impl<Idx: OrderedIndex, V> InversionMap<Idx, V> {
     /// An iterator over the inner ranges contained in this list.
-    pub fn iter(&self) -> Iter<Idx, V> {
-        Iter {
-            iter: self.ranges.iter(),
+    pub fn iter(&self) -> Iter<'_, Idx, V> {
+        Iter::new(&self.ranges)
+    }
+
+    /// An iterator over the inner ranges contained in this map, with mutable references to their
+    /// values.
+    pub fn iter_mut(&mut self) -> IterMut<'_, Idx, V> {
+        IterMut::new(self.ranges.values_mut())
+    }
+
+    /// An iterator over mutable references to the values of this map, in ascending order of their
+    /// ranges.
+    pub fn values_mut(&mut self) -> ValuesMut<'_, Idx, V> {
+        ValuesMut {
+            iter: self.ranges.values_mut().iter_mut(),
+        }
+    }
+
+    /// An iterator over every single index covered by this map along with its value, in
+    /// ascending order.
+    pub fn elements(&self) -> Elements<'_, Idx, V> {
+        Elements::new(&self.ranges)
+    }
+
+    /// An iterator over the entries overlapping the given range, in ascending order.
+    ///
+    /// The entries are yielded as is, so the first and last ones may reach past `range`, see
+    /// [`Self::range_clipped`] for a version that cuts them down.
+    pub fn range<R: RangeBounds<Idx>>(&self, range: R) -> Range<'_, Idx, V> {
+        self.range_impl(range, false)
+    }
+
+    /// An iterator over the entries overlapping the given range, in ascending order, with the first
+    /// and last ones clipped to `range`.
+    pub fn range_clipped<R: RangeBounds<Idx>>(&self, range: R) -> Range<'_, Idx, V> {
+        self.range_impl(range, true)
+    }
+
+    /// An iterator over the ranges inside of `bounds` that are not covered by this map, in
+    /// ascending order.
+    pub fn gaps<R: RangeBounds<Idx>>(&self, bounds: R) -> Gaps<'_, Idx, V> {
+        match bounds_to_range(bounds) {
+            Some(bounds) => Gaps {
+                entries: &self.ranges[self.overlapping(bounds.clone())],
+                bounds: Some(bounds.into_inner()),
+            },
+            None => Gaps {
+                entries: &[],
+                bounds: None,
+            },
+        }
+    }
+
+    fn range_impl<R: RangeBounds<Idx>>(&self, range: R, clip: bool) -> Range<'_, Idx, V> {
+        match bounds_to_range(range) {
+            Some(range) => Range {
+                iter: self.ranges[self.overlapping(range.clone())].iter(),
+                clip: clip.then_some(range),
+            },
+            None => Range {
+                iter: [].iter(),
+                clip: None,
+            },
         }
     }
 }
 
+impl<'il, Idx: OrderedIndex, V> IntoIterator for &'il InversionMap<Idx, V> {
+    type Item = <Self::IntoIter as Iterator>::Item;
+    type IntoIter = Iter<'il, Idx, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        Iter::new(&self.ranges)
+    }
+}
+
+impl<'il, Idx: OrderedIndex, V> IntoIterator for &'il mut InversionMap<Idx, V> {
+    type Item = <Self::IntoIter as Iterator>::Item;
+    type IntoIter = IterMut<'il, Idx, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        IterMut::new(self.ranges.values_mut())
+    }
+}
+
+/// A mutable iterator over the entries of a map, only the values can be modified.
 #[derive(Debug)]
-pub struct Iter<'il, Idx: OrderedIndex, V> {
-    iter: core::slice::Iter<'il, Entry<Idx, V>>,
+pub struct IterMut<'il, Idx, V> {
+    iter: slice::IterMut<'il, Entry<Idx, V>>,
 }
 
-impl<'il, Idx: OrderedIndex, V> Clone for Iter<'il, Idx, V> {
-    fn clone(&self) -> Self {
-        Self {
-            iter: self.iter.clone(),
+impl<'il, Idx: OrderedIndex, V> IterMut<'il, Idx, V> {
+    pub(crate) fn new(entries: &'il mut [Entry<Idx, V>]) -> Self {
+        IterMut {
+            iter: entries.iter_mut(),
         }
     }
 }
 
-impl<'a, Idx: OrderedIndex, V> Iterator for Iter<'a, Idx, V> {
-    type Item = (Range<Idx>, &'a V);
+impl<'il, Idx: OrderedIndex, V> Iterator for IterMut<'il, Idx, V> {
+    type Item = (RangeInclusive<Idx>, &'il mut V);
+    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
         self.iter
             .next()
-            .map(|entry| (entry.range.clone(), &entry.value))
+            .map(|entry| (entry.range(), &mut entry.value))
     }
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -39,21 +121,53 @@ impl<'a, Idx: OrderedIndex, V> Iterator for Iter<'a, Idx, V> {
     }
 }
 
-impl<Idx: OrderedIndex, V> FusedIterator for Iter<'_, Idx, V> {}
-impl<Idx: OrderedIndex, V> ExactSizeIterator for Iter<'_, Idx, V> {
+impl<Idx: OrderedIndex, V> DoubleEndedIterator for IterMut<'_, Idx, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next_back()
+            .map(|entry| (entry.range(), &mut entry.value))
+    }
+}
+
+impl<Idx: OrderedIndex, V> FusedIterator for IterMut<'_, Idx, V> {}
+impl<Idx: OrderedIndex, V> ExactSizeIterator for IterMut<'_, Idx, V> {
     #[inline]
     fn len(&self) -> usize {
         self.iter.len()
     }
 }
 
-impl<'il, Idx: OrderedIndex, V> IntoIterator for &'il InversionMap<Idx, V> {
-    type Item = <Self::IntoIter as Iterator>::Item;
-    type IntoIter = Iter<'il, Idx, V>;
-    fn into_iter(self) -> Self::IntoIter {
-        Iter {
-            iter: self.ranges.iter(),
-        }
+/// An iterator over mutable references to the values of a map, see [`InversionMap::values_mut`].
+#[derive(Debug)]
+pub struct ValuesMut<'il, Idx, V> {
+    iter: slice::IterMut<'il, Entry<Idx, V>>,
+}
+
+impl<'il, Idx, V> Iterator for ValuesMut<'il, Idx, V> {
+    type Item = &'il mut V;
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|entry| &mut entry.value)
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Idx, V> DoubleEndedIterator for ValuesMut<'_, Idx, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|entry| &mut entry.value)
+    }
+}
+
+impl<Idx, V> FusedIterator for ValuesMut<'_, Idx, V> {}
+impl<Idx, V> ExactSizeIterator for ValuesMut<'_, Idx, V> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.iter.len()
     }
 }
 
@@ -63,10 +177,10 @@ pub struct IntoIter<Idx: OrderedIndex, V> {
 }
 
 impl<Idx: OrderedIndex, V> Iterator for IntoIter<Idx, V> {
-    type Item = (Range<Idx>, V);
+    type Item = (RangeInclusive<Idx>, V);
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(|entry| (entry.range, entry.value))
+        self.iter.next().map(Into::into)
     }
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -74,6 +188,13 @@ impl<Idx: OrderedIndex, V> Iterator for IntoIter<Idx, V> {
     }
 }
 
+impl<Idx: OrderedIndex, V> DoubleEndedIterator for IntoIter<Idx, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(Into::into)
+    }
+}
+
 impl<Idx: OrderedIndex, V> FusedIterator for IntoIter<Idx, V> {}
 impl<Idx: OrderedIndex, V> ExactSizeIterator for IntoIter<Idx, V> {
     #[inline]
@@ -88,7 +209,244 @@ impl<Idx: OrderedIndex, V> IntoIterator for InversionMap<Idx, V> {
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
         IntoIter {
-            iter: self.ranges.into_iter(),
+            iter: self.ranges.into_entries().into_iter(),
+        }
+    }
+}
+
+/// An iterator over the individual indices covered by a map, see [`InversionMap::elements`].
+#[derive(Debug)]
+pub struct Elements<'il, Idx, V> {
+    // the entries not yet fully visited, `front` and `back` lie in the first and last of them
+    entries: &'il [Entry<Idx, V>],
+    front: Idx,
+    back: Idx,
+    remaining: u128,
+}
+
+impl<'il, Idx: OrderedIndex, V> Elements<'il, Idx, V> {
+    pub(crate) fn new(entries: &'il [Entry<Idx, V>]) -> Self {
+        Elements {
+            entries,
+            front: entries
+                .first()
+                .map_or_else(Idx::min_value, |entry| entry.start),
+            back: entries
+                .last()
+                .map_or_else(Idx::min_value, |entry| entry.end),
+            remaining: raw::count(entries),
+        }
+    }
+}
+
+impl<Idx: OrderedIndex, V> Clone for Elements<'_, Idx, V> {
+    fn clone(&self) -> Self {
+        Elements { ..*self }
+    }
+}
+
+impl<'il, Idx: OrderedIndex, V> Iterator for Elements<'il, Idx, V> {
+    type Item = (Idx, &'il V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (first, rest) = self.entries.split_first()?;
+        let index = self.front;
+        let end = if rest.is_empty() {
+            self.back
+        } else {
+            first.end
+        };
+        if index == end {
+            self.entries = rest;
+            if let Some(next) = rest.first() {
+                self.front = next.start;
+            }
+        } else {
+            // `index < end` so this can't overflow
+            self.front = successor(index).unwrap();
+        }
+        self.remaining = self.remaining.saturating_sub(1);
+        Some((index, &first.value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match usize::try_from(self.remaining) {
+            Ok(remaining) => (remaining, Some(remaining)),
+            Err(_) => (usize::MAX, None),
+        }
+    }
+}
+
+impl<Idx: OrderedIndex, V> DoubleEndedIterator for Elements<'_, Idx, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (last, rest) = self.entries.split_last()?;
+        let index = self.back;
+        let start = if rest.is_empty() {
+            self.front
+        } else {
+            last.start
+        };
+        if index == start {
+            self.entries = rest;
+            if let Some(next) = rest.last() {
+                self.back = next.end;
+            }
+        } else {
+            // `start < index` so this can't underflow
+            self.back = predecessor(index).unwrap();
+        }
+        self.remaining = self.remaining.saturating_sub(1);
+        Some((index, &last.value))
+    }
+}
+
+impl<Idx: OrderedIndex, V> FusedIterator for Elements<'_, Idx, V> {}
+
+/// An iterator over the entries of a map overlapping a range, see [`InversionMap::range`] and
+/// [`InversionMap::range_clipped`].
+#[derive(Debug)]
+pub struct Range<'il, Idx, V> {
+    iter: slice::Iter<'il, Entry<Idx, V>>,
+    // the range to clip the yielded entries to, if requested
+    clip: Option<RangeInclusive<Idx>>,
+}
+
+impl<Idx: OrderedIndex, V> Range<'_, Idx, V> {
+    fn clip(&self, range: RangeInclusive<Idx>) -> RangeInclusive<Idx> {
+        match &self.clip {
+            Some(clip) => {
+                let (start, end) = range.into_inner();
+                start.max(*clip.start())..=end.min(*clip.end())
+            }
+            None => range,
+        }
+    }
+}
+
+impl<Idx: OrderedIndex, V> Clone for Range<'_, Idx, V> {
+    fn clone(&self) -> Self {
+        Range {
+            iter: self.iter.clone(),
+            clip: self.clip.clone(),
+        }
+    }
+}
+
+impl<'il, Idx: OrderedIndex, V> Iterator for Range<'il, Idx, V> {
+    type Item = (RangeInclusive<Idx>, &'il V);
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.iter.next()?;
+        Some((self.clip(entry.range()), &entry.value))
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Idx: OrderedIndex, V> DoubleEndedIterator for Range<'_, Idx, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let entry = self.iter.next_back()?;
+        Some((self.clip(entry.range()), &entry.value))
+    }
+}
+
+impl<Idx: OrderedIndex, V> FusedIterator for Range<'_, Idx, V> {}
+impl<Idx: OrderedIndex, V> ExactSizeIterator for Range<'_, Idx, V> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+/// An iterator over the uncovered ranges of a map within some bounds, see [`InversionMap::gaps`].
+#[derive(Debug)]
+pub struct Gaps<'il, Idx, V> {
+    // the entries overlapping `bounds`
+    entries: &'il [Entry<Idx, V>],
+    // the part of the bounds left to look at, `None` once the iterator is exhausted
+    bounds: Option<(Idx, Idx)>,
+}
+
+impl<Idx: OrderedIndex, V> Clone for Gaps<'_, Idx, V> {
+    fn clone(&self) -> Self {
+        Gaps { ..*self }
+    }
+}
+
+impl<Idx: OrderedIndex, V> Iterator for Gaps<'_, Idx, V> {
+    type Item = RangeInclusive<Idx>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (mut start, end) = self.bounds?;
+        // skip the entries covering `start`, there might be multiple directly adjacent ones
+        while let [first, rest @ ..] = self.entries {
+            if start < first.start {
+                break;
+            }
+            self.entries = rest;
+            match successor(first.end) {
+                Some(next) if next <= end => start = next,
+                _ => {
+                    self.bounds = None;
+                    return None;
+                }
+            }
+        }
+        match self.entries.first() {
+            Some(first) => {
+                // the entry is skipped by the next call
+                self.bounds = Some((first.start, end));
+                // `start < first.start` so this can't underflow
+                Some(start..=predecessor(first.start).unwrap())
+            }
+            None => {
+                self.bounds = None;
+                Some(start..=end)
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.bounds {
+            Some(_) => (0, Some(self.entries.len() + 1)),
+            None => (0, Some(0)),
         }
     }
 }
+
+impl<Idx: OrderedIndex, V> DoubleEndedIterator for Gaps<'_, Idx, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (start, mut end) = self.bounds?;
+        while let [rest @ .., last] = self.entries {
+            if last.end < end {
+                break;
+            }
+            self.entries = rest;
+            match predecessor(last.start) {
+                Some(prev) if start <= prev => end = prev,
+                _ => {
+                    self.bounds = None;
+                    return None;
+                }
+            }
+        }
+        match self.entries.last() {
+            Some(last) => {
+                self.bounds = Some((start, last.end));
+                // `last.end < end` so this can't overflow
+                Some(successor(last.end).unwrap()..=end)
+            }
+            None => {
+                self.bounds = None;
+                Some(start..=end)
+            }
+        }
+    }
+}
+
+impl<Idx: OrderedIndex, V> FusedIterator for Gaps<'_, Idx, V> {}
@@ -0,0 +1,162 @@
+//! Linear merges over the sorted entry vectors of two maps with closures combining the values.
+
+use core::iter::FusedIterator;
+use core::ops::RangeInclusive;
+
+use alloc::vec::Vec;
+
+use crate::map::{Entry, InversionMap};
+use crate::util::{predecessor, successor};
+use crate::OrderedIndex;
+
+impl<Idx: OrderedIndex, V> InversionMap<Idx, V> {
+    /// Merges the entries of `self` and `other` into a new map, producing the values of the parts
+    /// covered by only `self`, only `other` or both with `left`, `right` and `both` respectively.
+    ///
+    /// Each closure is passed the part of the range it produces a value for. Entries are cut
+    /// wherever an entry of either map starts or ends, so the result might hold directly adjacent
+    /// entries.
+    pub fn union_with<U, W>(
+        &self,
+        other: &InversionMap<Idx, U>,
+        mut left: impl FnMut(RangeInclusive<Idx>, &V) -> W,
+        mut right: impl FnMut(RangeInclusive<Idx>, &U) -> W,
+        mut both: impl FnMut(RangeInclusive<Idx>, &V, &U) -> W,
+    ) -> InversionMap<Idx, W> {
+        merge(self, other, |range, a, b| match (a, b) {
+            (Some(a), Some(b)) => Some(both(range, a, b)),
+            (Some(a), None) => Some(left(range, a)),
+            (None, Some(b)) => Some(right(range, b)),
+            (None, None) => None,
+        })
+    }
+
+    /// Merges the parts covered by both `self` and `other` into a new map, producing their values
+    /// with `both`, see [`Self::union_with`].
+    pub fn intersection_with<U, W>(
+        &self,
+        other: &InversionMap<Idx, U>,
+        mut both: impl FnMut(RangeInclusive<Idx>, &V, &U) -> W,
+    ) -> InversionMap<Idx, W> {
+        merge(self, other, |range, a, b| Some(both(range, a?, b?)))
+    }
+
+    /// Collects the parts covered by `self` but not by `other` into a new map, producing their
+    /// values with `left`, see [`Self::union_with`].
+    pub fn difference_with<U, W>(
+        &self,
+        other: &InversionMap<Idx, U>,
+        mut left: impl FnMut(RangeInclusive<Idx>, &V) -> W,
+    ) -> InversionMap<Idx, W> {
+        merge(self, other, |range, a, b| match (a, b) {
+            (Some(a), None) => Some(left(range, a)),
+            _ => None,
+        })
+    }
+
+    /// Collects the parts covered by exactly one of `self` and `other` into a new map, producing
+    /// their values with `left` and `right` respectively, see [`Self::union_with`].
+    pub fn symmetric_difference_with<U, W>(
+        &self,
+        other: &InversionMap<Idx, U>,
+        mut left: impl FnMut(RangeInclusive<Idx>, &V) -> W,
+        mut right: impl FnMut(RangeInclusive<Idx>, &U) -> W,
+    ) -> InversionMap<Idx, W> {
+        merge(self, other, |range, a, b| match (a, b) {
+            (Some(a), None) => Some(left(range, a)),
+            (None, Some(b)) => Some(right(range, b)),
+            _ => None,
+        })
+    }
+}
+
+/// Builds a map out of the segments of `a` and `b` that `f` produces a value for.
+fn merge<Idx: OrderedIndex, V, U, W>(
+    a: &InversionMap<Idx, V>,
+    b: &InversionMap<Idx, U>,
+    mut f: impl FnMut(RangeInclusive<Idx>, Option<&V>, Option<&U>) -> Option<W>,
+) -> InversionMap<Idx, W> {
+    let segments = Segments {
+        a: &a.ranges,
+        b: &b.ranges,
+        pos: Some(Idx::min_value()),
+    };
+    let ranges: Vec<_> = segments
+        .filter_map(|(range, a, b)| {
+            let (start, end) = range.clone().into_inner();
+            let value = f(range, a, b)?;
+            Some(Entry { start, end, value })
+        })
+        .collect();
+    InversionMap {
+        ranges: ranges.into(),
+    }
+}
+
+/// Walks the covered parts of two maps, yielding the maximal segments throughout which neither
+/// map changes entries, along with the values of the entries covering them.
+struct Segments<'a, Idx, V, U> {
+    a: &'a [Entry<Idx, V>],
+    b: &'a [Entry<Idx, U>],
+    // the next index to look at, `None` once the iterator is exhausted
+    pos: Option<Idx>,
+}
+
+/// Drops all entries ending before `pos`.
+fn skip_before<Idx: OrderedIndex, T>(entries: &mut &[Entry<Idx, T>], pos: Idx) {
+    while let [first, rest @ ..] = *entries {
+        if first.end >= pos {
+            break;
+        }
+        *entries = rest;
+    }
+}
+
+/// Returns the last index of the segment at `pos` as far as `entry` is concerned, along with the
+/// entry if it covers `pos`.
+fn segment_end<Idx: OrderedIndex, T>(
+    entry: Option<&Entry<Idx, T>>,
+    pos: Idx,
+) -> (Option<Idx>, Option<&Entry<Idx, T>>) {
+    match entry {
+        Some(entry) if entry.start <= pos => (Some(entry.end), Some(entry)),
+        // `pos < entry.start` so this can't underflow
+        Some(entry) => (Some(predecessor(entry.start).unwrap()), None),
+        None => (None, None),
+    }
+}
+
+impl<'a, Idx: OrderedIndex, V, U> Iterator for Segments<'a, Idx, V, U> {
+    type Item = (RangeInclusive<Idx>, Option<&'a V>, Option<&'a U>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let pos = self.pos?;
+            skip_before(&mut self.a, pos);
+            skip_before(&mut self.b, pos);
+            let (end_a, in_a) = segment_end(self.a.first(), pos);
+            let (end_b, in_b) = segment_end(self.b.first(), pos);
+            if in_a.is_none() && in_b.is_none() {
+                // jump to the start of the next entry, if any
+                self.pos = match (self.a.first(), self.b.first()) {
+                    (Some(a), Some(b)) => Some(a.start.min(b.start)),
+                    (a, b) => a.map(|a| a.start).or(b.map(|b| b.start)),
+                };
+                continue;
+            }
+            let end = match (end_a, end_b) {
+                (Some(a), Some(b)) => a.min(b),
+                // at least one of the maps covers `pos`
+                (a, b) => a.or(b).unwrap(),
+            };
+            self.pos = successor(end);
+            return Some((
+                pos..=end,
+                in_a.map(|entry| &entry.value),
+                in_b.map(|entry| &entry.value),
+            ));
+        }
+    }
+}
+
+impl<Idx: OrderedIndex, V, U> FusedIterator for Segments<'_, Idx, V, U> {}
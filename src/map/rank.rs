@@ -0,0 +1,185 @@
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+use alloc::vec::Vec;
+
+use crate::map::{Entry, InversionMap};
+use crate::raw;
+use crate::util::{advance, range_count};
+use crate::OrderedIndex;
+
+/// The entries of a map along with the prefix counts of their ranges.
+///
+/// The counts are kept up to date across all mutations, which have to go through
+/// [`Ranges::update`], so that rank and select queries take `O(log n)`.
+#[derive(Clone)]
+pub(crate) struct Ranges<Idx, V> {
+    entries: Vec<Entry<Idx, V>>,
+    // `prefix[i]` is the number of indices covered by `entries[..i]`, which can't overflow as
+    // `entries[i]` covers at least one more index
+    prefix: Vec<u128>,
+}
+
+impl<Idx, V> Ranges<Idx, V> {
+    pub(crate) const fn new() -> Self {
+        Ranges {
+            entries: Vec::new(),
+            prefix: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Ranges {
+            entries: Vec::with_capacity(capacity),
+            prefix: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn into_entries(self) -> Vec<Entry<Idx, V>> {
+        self.entries
+    }
+
+    /// Grants mutable access to the entries for changing their values only, the prefix counts
+    /// are not updated.
+    pub(crate) fn values_mut(&mut self) -> &mut [Entry<Idx, V>] {
+        &mut self.entries
+    }
+}
+
+impl<Idx: OrderedIndex, V> Ranges<Idx, V> {
+    /// Runs `f` on the entries, recounting the prefixes of all entries from `from` on afterwards.
+    ///
+    /// `f` must leave the entries before `from` untouched.
+    pub(crate) fn update<R>(
+        &mut self,
+        from: usize,
+        f: impl FnOnce(&mut Vec<Entry<Idx, V>>) -> R,
+    ) -> R {
+        let res = f(&mut self.entries);
+        let from = from.min(self.entries.len());
+        self.prefix.truncate(from);
+        let mut acc = self.before(from);
+        self.prefix.extend(self.entries[from..].iter().map(|entry| {
+            let before = acc;
+            acc = acc.saturating_add(range_count(&entry.range()));
+            before
+        }));
+        res
+    }
+
+    /// Returns the number of indices covered by `entries[..idx]`, saturating at `u128::MAX`.
+    fn before(&self, idx: usize) -> u128 {
+        match idx.checked_sub(1) {
+            Some(last) => {
+                self.prefix[last].saturating_add(range_count(&self.entries[last].range()))
+            }
+            None => 0,
+        }
+    }
+
+    /// Returns the position from which on a mutation of the entries touching `index` has to
+    /// recount the prefixes.
+    pub(crate) fn affected_from(&self, index: Idx) -> usize {
+        match raw::binary_search(&self.entries, index) {
+            // the entry before might get merged with or extended up to `index`
+            Ok(idx) | Err(idx) => idx.saturating_sub(1),
+        }
+    }
+
+    /// Returns the number of indices covered, saturating at `u128::MAX`.
+    pub(crate) fn count(&self) -> u128 {
+        self.before(self.entries.len())
+    }
+
+    /// Returns the number of covered indices smaller than `index`.
+    pub(crate) fn rank(&self, index: Idx) -> u128 {
+        match raw::binary_search(&self.entries, index) {
+            Ok(idx) => {
+                let offset = Idx::steps_between(self.entries[idx].start, index).unwrap();
+                self.prefix[idx] + offset
+            }
+            Err(idx) => self.before(idx),
+        }
+    }
+
+    /// Returns the `n`th covered index in ascending order, counting from zero, along with its
+    /// entry.
+    pub(crate) fn select(&self, n: u128) -> Option<(Idx, &Entry<Idx, V>)> {
+        // the last entry starting at or before the `n`th covered index
+        let idx = self
+            .prefix
+            .partition_point(|&count| count <= n)
+            .checked_sub(1)?;
+        let entry = &self.entries[idx];
+        let index = advance(entry.start, n - self.prefix[idx])?;
+        (index <= entry.end).then_some((index, entry))
+    }
+}
+
+impl<Idx, V> Default for Ranges<Idx, V> {
+    fn default() -> Self {
+        Ranges::new()
+    }
+}
+
+impl<Idx, V> Deref for Ranges<Idx, V> {
+    type Target = Vec<Entry<Idx, V>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+impl<Idx: OrderedIndex, V> From<Vec<Entry<Idx, V>>> for Ranges<Idx, V> {
+    fn from(entries: Vec<Entry<Idx, V>>) -> Self {
+        let mut ranges = Ranges {
+            entries,
+            prefix: Vec::new(),
+        };
+        ranges.update(0, |_| ());
+        ranges
+    }
+}
+
+impl<Idx: OrderedIndex, V> FromIterator<Entry<Idx, V>> for Ranges<Idx, V> {
+    fn from_iter<T: IntoIterator<Item = Entry<Idx, V>>>(iter: T) -> Self {
+        Vec::from_iter(iter).into()
+    }
+}
+
+// the prefix counts follow from the entries, so they are left out of comparisons
+impl<Idx: PartialEq, V: PartialEq> PartialEq for Ranges<Idx, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl<Idx: Eq, V: Eq> Eq for Ranges<Idx, V> {}
+
+impl<Idx: Hash, V: Hash> Hash for Ranges<Idx, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.entries.hash(state);
+    }
+}
+
+impl<Idx: fmt::Debug, V: fmt::Debug> fmt::Debug for Ranges<Idx, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.entries.fmt(f)
+    }
+}
+
+impl<Idx: OrderedIndex, V> InversionMap<Idx, V> {
+    /// Returns the number of covered indices smaller than `index` in `O(log n)`.
+    pub fn rank(&self, index: Idx) -> u128 {
+        self.ranges.rank(index)
+    }
+
+    /// Returns the `n`th covered index in ascending order, counting from zero, along with its
+    /// value in `O(log n)`.
+    pub fn select(&self, n: u128) -> Option<(Idx, &V)> {
+        self.ranges
+            .select(n)
+            .map(|(index, entry)| (index, &entry.value))
+    }
+}
@@ -1,11 +1,16 @@
 use super::*;
+use crate::testing::{random_range, xorshift};
 
 macro_rules! im {
     ($($range:expr => $val:expr),* $(,)?) => {
         InversionMap {
             ranges: alloc::vec![
-                $(Entry { range: $range, value: $val }),*
-            ],
+                $({
+                    let (start, end) = bounds_to_range($range).unwrap().into_inner();
+                    Entry { start, end, value: $val }
+                }),*
+            ]
+            .into(),
         }
     };
 }
@@ -190,6 +195,15 @@ fn add_range_ignore_max_range() {
     assert_eq!(im, im![0..10 => 0, 20..30 => 0, 40..50 => 0, 60..70 => 0]);
 }
 
+#[test]
+fn add_range_max_value() {
+    let mut im = im![0u8..10 => 0];
+    im.add_range(250.., 1);
+    assert_eq!(im, im![0..10 => 0, 250..=255 => 1]);
+    im.add_range(..=u8::MAX, 2);
+    assert_eq!(im, im![0..=255 => 2]);
+}
+
 #[test]
 fn add_range_ignore_min_range() {
     // test to make sure we dont underflow
@@ -322,6 +336,17 @@ fn insert_range_ignore_max_range() {
     assert_eq!(im, im![0..10 => 0, 20..30 => 0, 40..50 => 0, 60..70 => 0]);
 }
 
+#[test]
+fn insert_range_max_value() {
+    let mut im = im![0u8..10 => 0, 240..=255 => 0];
+    im.insert_range(250..=255, 1);
+    assert_eq!(im, im![0..10 => 0, 240..250 => 0, 250..=255 => 1]);
+    im.insert_range(5..=u8::MAX, 2);
+    assert_eq!(im, im![0..5 => 0, 5..=255 => 2]);
+    assert_eq!(im.lookup(255), Some((5..=255, &2)));
+    assert_eq!(im.end(), Some(255));
+}
+
 #[test]
 fn insert_range_ignore_min_range() {
     // test to make sure we dont underflow
@@ -394,6 +419,33 @@ fn remove_range_start() {
     assert_eq!(im, im![50..100 => 2]);
 }
 
+#[test]
+fn remove_range_max_value() {
+    let mut im = im![0u8..=255 => 0];
+    im.remove_range(100..=255, |_, _| 1, |_, _| 2);
+    assert_eq!(im, im![0..100 => 1]);
+    let mut im = im![0u8..=255 => 0];
+    im.remove_range(100..200, |_, _| 1, |_, _| 2);
+    assert_eq!(im, im![0..100 => 1, 200..=255 => 2]);
+}
+
+#[test]
+fn split_max_value() {
+    let mut im = im![0u8..=255 => 0];
+    assert_eq!(im.split(255), Some((0, 1)));
+    assert_eq!(im, im![0..255 => 0, 255..=255 => 0]);
+}
+
+#[test]
+fn lookup_range() {
+    let im = im![1..10 => 0, 15..26 => 1, 61..100 => 2];
+    let entries = im.lookup_range(12..20).unwrap();
+    assert_eq!(entries.iter().collect::<Vec<_>>(), [(15..=25, &1)]);
+    let entries = im.lookup_range(5..70).unwrap();
+    assert_eq!(entries.len(), 3);
+    assert!(im.lookup_range(10..15).is_none());
+}
+
 // #[test]
 // fn is_subset() {
 //     let im = im![1..10, 15..26, 61..100];
@@ -494,3 +546,319 @@ fn test_bitor() {
         // FIXME should be im![0..5 => 0xAAFF, 5..10 => 0xAAFF, 10..12 => 0xaaaa, 12..15 => 0xAAFF,...]
     );
 }
+
+#[test]
+fn count() {
+    assert_eq!(im![0..5 => 0, 10..=10 => 1].count(), 6);
+    assert_eq!(im![..=u64::MAX => 0].count(), 1 << 64);
+}
+
+#[test]
+fn rank_select() {
+    let im = im![0..5 => 'a', 10..=10 => 'b', 20..25 => 'c'];
+    assert_eq!(im.count(), 11);
+    assert_eq!(im.select(0), Some((0, &'a')));
+    assert_eq!(im.select(5), Some((10, &'b')));
+    assert_eq!(im.select(8), Some((22, &'c')));
+    assert_eq!(im.select(11), None);
+    assert_eq!(im.rank(7), 5);
+    assert_eq!(im.rank(11), 6);
+    assert_eq!(im.rank(u32::MAX), 11);
+}
+
+#[test]
+fn rank_select_follow_mutations() {
+    fn check(im: &InversionMap<u8, u8>) {
+        let elements: Vec<_> = im.elements().map(|(i, &v)| (i, v)).collect();
+        assert_eq!(im.count(), elements.len() as u128);
+        for (n, &(i, v)) in elements.iter().enumerate() {
+            assert_eq!(im.select(n as u128), Some((i, &v)));
+            assert_eq!(im.rank(i), n as u128);
+        }
+        assert_eq!(im.select(elements.len() as u128), None);
+    }
+
+    let mut im = InversionMap::<u8, u8>::new();
+    im.insert_range(10..20, 0);
+    im.add_range(200.., 1);
+    im.insert_unit(15, 2);
+    check(&im);
+    im.add_range(18..30, 3);
+    im.add_unit(5, 4);
+    check(&im);
+    im.remove_range(12..=16, |_, &v| v, |_, &v| v);
+    im.split(25);
+    check(&im);
+    im.insert_range(0..=9, 0);
+    im.coalesce_by(|_, _| Some(5));
+    check(&im);
+
+    let mut cursor = im.cursor_mut_at(100);
+    cursor.insert_before(100..110, 6).unwrap();
+    cursor.insert_after(250..=255, 7).unwrap_err();
+    cursor.split_current(220);
+    check(&im);
+    let mut cursor = im.cursor_mut_at(0);
+    cursor.remove_current();
+    check(&im);
+    im.clear();
+    check(&im);
+}
+
+#[test]
+fn elements() {
+    let im = im![0..2 => 'a', 254..=255u8 => 'b'];
+    assert!(im
+        .elements()
+        .eq([(0, &'a'), (1, &'a'), (254, &'b'), (255, &'b')]));
+    assert!(im.elements().rev().map(|(i, _)| i).eq([255, 254, 1, 0]));
+}
+
+#[test]
+fn range() {
+    let im = im![0..10 => 'a', 20..30u8 => 'b', 40..50 => 'c'];
+    assert!(im.range(5..25).eq([(0..=9, &'a'), (20..=29, &'b')]));
+    assert!(im.range_clipped(5..25).eq([(5..=9, &'a'), (20..=24, &'b')]));
+    assert!(im
+        .range_clipped(5..)
+        .rev()
+        .eq([(40..=49, &'c'), (20..=29, &'b'), (5..=9, &'a')]));
+    assert!(im.iter().rev().map(|(_, v)| *v).eq(['c', 'b', 'a']));
+    assert!(im.into_iter().rev().map(|(_, v)| v).eq(['c', 'b', 'a']));
+}
+
+#[test]
+fn cursor() {
+    let im = im![0..10 => 'a', 20..30u8 => 'b', 40..50 => 'c'];
+    let mut cursor = im.cursor_at(25);
+    assert_eq!(cursor.current(), Some((20..=29, &'b')));
+    assert_eq!(cursor.peek_prev(), Some((0..=9, &'a')));
+    assert_eq!(cursor.peek_next(), Some((40..=49, &'c')));
+    cursor.move_next();
+    cursor.move_next();
+    assert_eq!((cursor.index(), cursor.current()), (None, None));
+    cursor.move_next();
+    assert_eq!(cursor.current(), Some((0..=9, &'a')));
+    cursor.move_prev();
+    assert_eq!(cursor.peek_prev(), Some((40..=49, &'c')));
+
+    // positions between entries point at the next one
+    cursor.seek(15);
+    assert_eq!(cursor.index(), Some(1));
+    cursor.seek(200);
+    assert_eq!(cursor.index(), None);
+}
+
+#[test]
+fn cursor_mut() {
+    let mut im = im![0..10 => 'a', 20..30u8 => 'b', 40..50 => 'c'];
+    let mut cursor = im.cursor_mut_at(20);
+    assert_eq!(cursor.set_value('B'), Some('b'));
+    assert_eq!(cursor.insert_before(10..20, 'x'), Ok(()));
+    assert_eq!(cursor.insert_after(30..=35, 'y'), Ok(()));
+    assert_eq!(cursor.current(), Some((20..=29, &'B')));
+    // overlapping the neighbours is rejected and hands the value back
+    assert_eq!(
+        cursor
+            .insert_before(15..20, 'z')
+            .map_err(|e| e.into_value()),
+        Err('z')
+    );
+    assert!(cursor.insert_after(29..31, 'z').is_err());
+
+    assert!(cursor.split_current(25));
+    assert!(!cursor.split_current(20));
+    assert!(!cursor.split_current(26));
+    assert_eq!(cursor.current(), Some((20..=24, &'B')));
+    cursor.move_next();
+    assert_eq!(cursor.remove_current(), Some((25..=29, 'B')));
+    assert_eq!(cursor.current(), Some((30..=35, &'y')));
+
+    cursor.seek(255);
+    assert_eq!(cursor.remove_current(), None);
+    // empty ranges are ignored
+    assert_eq!(cursor.insert_after(..0, 'x'), Ok(()));
+    assert_eq!(cursor.insert_before(250.., 'e'), Ok(()));
+    assert_eq!(cursor.index(), None);
+    assert!(cursor.current_mut().is_none());
+    assert!(im.iter().eq([
+        (0..=9, &'a'),
+        (10..=19, &'x'),
+        (20..=24, &'B'),
+        (30..=35, &'y'),
+        (40..=49, &'c'),
+        (250..=255, &'e'),
+    ]));
+
+    // `cursor_mut` starts at the first entry, or the ghost position of an empty map
+    let mut cursor = im.cursor_mut();
+    assert_eq!(cursor.remove_current(), Some((0..=9, 'a')));
+    assert_eq!(im.cursor().current(), Some((10..=19, &'x')));
+    let mut empty = InversionMap::<u8, char>::new();
+    let mut cursor = empty.cursor_mut();
+    assert_eq!(cursor.index(), None);
+    assert_eq!(cursor.insert_after(5..10, 'a'), Ok(()));
+    assert_eq!(cursor.peek_next(), Some((5..=9, &'a')));
+}
+
+#[test]
+fn mutable_values() {
+    let mut im = im![0..10 => 1, 20..30u8 => 2, 40..50 => 3];
+    *im.get_mut(5).unwrap() += 10;
+    assert_eq!(im.get_mut(15), None);
+    let (range, value) = im.lookup_mut(45).unwrap();
+    assert_eq!(range, 40..=49);
+    *value = 30;
+
+    let mut entries = im.lookup_range_mut(25..45).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().map(|(r, _)| r).eq([20..=29, 40..=49]));
+    for (_, value) in entries.iter_mut() {
+        *value *= 2;
+    }
+    for (_, value) in entries {
+        *value += 1;
+    }
+    assert!(im.lookup_range_mut(10..20).is_none());
+    assert!(im.iter().map(|(_, v)| *v).eq([11, 5, 61]));
+
+    for (range, value) in &mut im {
+        *value += *range.start();
+    }
+    if let Some((_, value)) = im.iter_mut().next_back() {
+        *value = 0;
+    }
+    im.values_mut().for_each(|value| *value += 1);
+    assert!(im.iter().eq([(0..=9, &12), (20..=29, &26), (40..=49, &1)]));
+}
+
+#[test]
+fn coalesce() {
+    let mut im = im![0..5 => 'a', 5..9 => 'a', 9..=9 => 'b', 11..15u8 => 'b', 15..20 => 'b'];
+    im.coalesce();
+    assert_eq!(im, im![0..9 => 'a', 9..=9 => 'b', 11..20 => 'b']);
+
+    let mut im = im![0..5 => 1, 5..9 => 2, 9..=9 => 30, 11..15u8 => 4];
+    im.coalesce_by(|a, b| (a + b < 10).then_some(a + b));
+    assert_eq!(im, im![0..9 => 3, 9..=9 => 30, 11..15 => 4]);
+}
+
+#[test]
+fn coalescing_map() {
+    let mut seed = 0x6a09_e667;
+    let mut cm = CoalescingMap::new();
+    let mut im = InversionMap::new();
+    for _ in 0..2000 {
+        let range = random_range(&mut seed, 256);
+        let a = *range.start();
+        let value = xorshift(&mut seed) % 3;
+        match xorshift(&mut seed) % 5 {
+            0 => {
+                cm.insert_range(range.clone(), value);
+                im.insert_range(range, value);
+            }
+            1 => {
+                cm.add_range(range.clone(), value);
+                im.add_range(range, value);
+            }
+            2 => {
+                cm.insert_unit(a, value);
+                im.insert_unit(a, value);
+            }
+            3 => {
+                cm.add_unit(a, value);
+                im.add_unit(a, value);
+            }
+            _ => {
+                cm.remove_range(range.clone());
+                im.remove_range(range, |_, v| *v, |_, v| *v);
+            }
+        }
+        im.coalesce();
+        assert_eq!(*cm, im);
+    }
+    assert_eq!(cm, CoalescingMap::from(im));
+}
+
+#[test]
+fn gaps() {
+    let im = im![0..5 => 'a', 5..10 => 'b', 20..30u8 => 'a'];
+    assert!(im.gaps(..).eq([10..=19, 30..=255]));
+    assert!(im.gaps(..).rev().eq([30..=255, 10..=19]));
+    assert!(im.gaps(3..8).eq([]));
+    assert!(im.gaps(3..=12).eq([10..=12]));
+    #[allow(clippy::reversed_empty_ranges)]
+    let empty = im.gaps(12..3);
+    assert!(empty.eq([]));
+    assert!(im.complement_within(..15).iter().eq([10..=14]));
+}
+
+#[test]
+fn merge_with() {
+    let a = im![0..10 => "a", 20..30u8 => "b"];
+    let b = im![5..25 => 1u32];
+    let union = a.union_with(
+        &b,
+        |_, a| a.len(),
+        |r, &b| r.count() + b as usize,
+        |_, a, &b| a.len() + b as usize + 10,
+    );
+    assert!(union.iter().eq([
+        (0..=4, &1),
+        (5..=9, &12),
+        (10..=19, &11),
+        (20..=24, &12),
+        (25..=29, &1),
+    ]));
+    let intersection = a.intersection_with(&b, |r, a, _| (r, *a));
+    assert!(intersection
+        .iter()
+        .map(|(_, v)| v.clone())
+        .eq([(5..=9, "a"), (20..=24, "b")]));
+    assert!(a
+        .difference_with(&b, |_, a| *a)
+        .iter()
+        .eq([(0..=4, &"a"), (25..=29, &"b")]));
+    assert!(a
+        .symmetric_difference_with(&b, |_, _| 'l', |_, _| 'r')
+        .iter()
+        .eq([(0..=4, &'l'), (10..=19, &'r'), (25..=29, &'l')]));
+}
+
+#[test]
+fn merge_with_matches_per_index() {
+    let mut seed = 0x7f4a_7c15;
+    let random_map = |seed: &mut u32| {
+        let mut im = InversionMap::<u8, u32>::new();
+        for _ in 0..xorshift(seed) % 10 {
+            im.insert_range(random_range(seed, 60), xorshift(seed) % 4);
+        }
+        im
+    };
+    for _ in 0..300 {
+        let (a, b) = (random_map(&mut seed), random_map(&mut seed));
+        let union = a.union_with(&b, |_, &a| a, |_, &b| b * 10, |_, &a, &b| a + b * 10);
+        let intersection = a.intersection_with(&b, |_, &a, &b| a + b * 10);
+        let difference = a.difference_with(&b, |_, &a| a);
+        let symmetric = a.symmetric_difference_with(&b, |_, &a| a, |_, &b| b * 10);
+        for i in 0..=255u8 {
+            let (va, vb) = (a.lookup(i).map(|(_, v)| *v), b.lookup(i).map(|(_, v)| *v));
+            let get = |im: &InversionMap<u8, u32>| im.lookup(i).map(|(_, v)| *v);
+            let both = va.zip(vb).map(|(a, b)| a + b * 10);
+            assert_eq!(get(&union), both.or(va).or(vb.map(|b| b * 10)));
+            assert_eq!(get(&intersection), both);
+            assert_eq!(get(&difference), va.filter(|_| vb.is_none()));
+            assert_eq!(
+                get(&symmetric),
+                match (va, vb) {
+                    (Some(a), None) => Some(a),
+                    (None, Some(b)) => Some(b * 10),
+                    _ => None,
+                }
+            );
+        }
+        for im in [&union, &intersection, &difference, &symmetric] {
+            assert!(im.ranges.windows(2).all(|w| w[0].end < w[1].start));
+        }
+    }
+}
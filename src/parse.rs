@@ -0,0 +1,261 @@
+//! A textual syntax for lists and maps, as used for page or port selections.
+//!
+//! Lists are written as comma separated items, each either a single index like `7`, an inclusive
+//! range like `1-5`, or a range open to the end of the index domain like `100-`. Maps append
+//! `=value` to every item, like `1-5=a,7=b`. Whitespace around items, bounds and values is
+//! ignored.
+//!
+//! Within values, `,`, `=` and `\` are escaped with a backslash, like `1=a\,b` for the value
+//! `a,b`, so that any value prints back into parsable text. Whitespace surrounding a value is
+//! still trimmed, so values starting or ending with whitespace do not survive a round trip.
+//!
+//! The dash separating the bounds is the first one following the start of an item, so negative
+//! indices can be written as `-10--5`.
+
+use core::convert::Infallible;
+use core::fmt::{self, Write};
+use core::ops::RangeInclusive;
+use core::str::FromStr;
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+
+use crate::{InversionList, InversionMap, OrderedIndex};
+
+#[cfg(test)]
+mod test;
+
+/// An error encountered while parsing the textual syntax of a list or map.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError<I, V = Infallible> {
+    /// The byte offset of the offending item or bound.
+    pub position: usize,
+    pub kind: ParseErrorKind<I, V>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind<I, V = Infallible> {
+    /// An item was empty, as in `1,,3`.
+    EmptyItem,
+    /// A bound failed to parse, for example because it overflows the index type.
+    InvalidIndex(I),
+    /// A range ended before it started.
+    ReversedRange,
+    /// A map item had no `=value`.
+    MissingValue,
+    /// A value of a map item failed to parse.
+    InvalidValue(V),
+}
+
+impl<I: fmt::Display, V: fmt::Display> fmt::Display for ParseError<I, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::EmptyItem => f.write_str("empty item")?,
+            ParseErrorKind::InvalidIndex(err) => write!(f, "invalid index: {}", err)?,
+            ParseErrorKind::ReversedRange => f.write_str("range end lies before its start")?,
+            ParseErrorKind::MissingValue => f.write_str("missing value")?,
+            ParseErrorKind::InvalidValue(err) => write!(f, "invalid value: {}", err)?,
+        }
+        write!(f, " at position {}", self.position)
+    }
+}
+
+impl<I: fmt::Debug + fmt::Display, V: fmt::Debug + fmt::Display> core::error::Error
+    for ParseError<I, V>
+{
+}
+
+/// Returns the byte offset of the first `c` in `s` that is not escaped with a backslash.
+fn find_unescaped(s: &str, c: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, ch) in s.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == c {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Removes the backslashes escaping the characters of a value.
+fn unescape(s: &str) -> Cow<'_, str> {
+    if !s.contains('\\') {
+        return Cow::Borrowed(s);
+    }
+    let mut res = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        // a trailing backslash escapes nothing and is kept as is
+        res.push(match c {
+            '\\' => chars.next().unwrap_or(c),
+            c => c,
+        });
+    }
+    Cow::Owned(res)
+}
+
+/// Escapes the characters that would otherwise end a value while writing it.
+struct Escaped<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl Write for Escaped<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut last = 0;
+        for (i, _) in s.match_indices(['\\', ',', '=']) {
+            self.0.write_str(&s[last..i])?;
+            self.0.write_char('\\')?;
+            last = i;
+        }
+        self.0.write_str(&s[last..])
+    }
+}
+
+/// Yields every comma separated item of `s` along with its byte offset, skipping escaped commas.
+fn items(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut offset = 0;
+    // an empty input has no items rather than a single empty one
+    let mut rest = (!s.trim().is_empty()).then_some(s);
+    core::iter::from_fn(move || {
+        let s = rest?;
+        let start = offset;
+        match find_unescaped(s, ',') {
+            Some(comma) => {
+                rest = Some(&s[comma + 1..]);
+                offset += comma + 1;
+                Some((start, &s[..comma]))
+            }
+            None => {
+                rest = None;
+                Some((start, s))
+            }
+        }
+    })
+}
+
+/// Trims `s`, moving `offset` past the leading whitespace.
+fn trim(offset: usize, s: &str) -> (usize, &str) {
+    let trimmed = s.trim_start();
+    (offset + s.len() - trimmed.len(), trimmed.trim_end())
+}
+
+fn parse_range<Idx: OrderedIndex + FromStr, V>(
+    offset: usize,
+    item: &str,
+) -> Result<RangeInclusive<Idx>, ParseError<Idx::Err, V>> {
+    let (offset, item) = trim(offset, item);
+    let error = |position, kind| ParseError { position, kind };
+    if item.is_empty() {
+        return Err(error(offset, ParseErrorKind::EmptyItem));
+    }
+    let parse = |offset, s| {
+        let (offset, s) = trim(offset, s);
+        s.parse::<Idx>()
+            .map_err(|err| error(offset, ParseErrorKind::InvalidIndex(err)))
+    };
+    // skip the first character so that a leading minus sign is not taken as the separator
+    let first_len = item.chars().next().map_or(0, char::len_utf8);
+    let Some(dash) = item[first_len..].find('-').map(|dash| dash + first_len) else {
+        let index = parse(offset, item)?;
+        return Ok(index..=index);
+    };
+    let start = parse(offset, &item[..dash])?;
+    let end = match &item[dash + 1..] {
+        end if end.trim().is_empty() => Idx::max_value(),
+        end => parse(offset + dash + 1, end)?,
+    };
+    if end < start {
+        return Err(error(offset, ParseErrorKind::ReversedRange));
+    }
+    Ok(start..=end)
+}
+
+/// Writes a single range, open ended if it reaches [`OrderedIndex::max_value()`].
+fn write_range<Idx: OrderedIndex + fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    range: RangeInclusive<Idx>,
+) -> fmt::Result {
+    let (start, end) = range.into_inner();
+    if start == end {
+        write!(f, "{}", start)
+    } else if end == Idx::max_value() {
+        write!(f, "{}-", start)
+    } else {
+        write!(f, "{}-{}", start, end)
+    }
+}
+
+/// Parses the comma separated range syntax, merging overlapping and adjacent items.
+impl<Idx: OrderedIndex + FromStr> FromStr for InversionList<Idx> {
+    type Err = ParseError<Idx::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut list = InversionList::new();
+        for (offset, item) in items(s) {
+            list.add_range(parse_range::<Idx, _>(offset, item)?);
+        }
+        Ok(list)
+    }
+}
+
+/// Writes the list in the comma separated range syntax.
+impl<Idx: OrderedIndex + fmt::Display> fmt::Display for InversionList<Idx> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut separator = "";
+        for range in self.iter() {
+            f.write_str(separator)?;
+            write_range(f, range)?;
+            separator = ",";
+        }
+        Ok(())
+    }
+}
+
+/// Parses comma separated `range=value` items, later items overwriting earlier ones where they
+/// overlap. Escaped characters in values are unescaped before parsing them.
+impl<Idx: OrderedIndex + FromStr, V: Clone + FromStr> FromStr for InversionMap<Idx, V> {
+    type Err = ParseError<Idx::Err, V::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut map = InversionMap::new();
+        for (offset, item) in items(s) {
+            let Some((range, value)) =
+                find_unescaped(item, '=').map(|eq| (&item[..eq], &item[eq + 1..]))
+            else {
+                let (offset, item) = trim(offset, item);
+                return Err(ParseError {
+                    position: offset + item.len(),
+                    kind: if item.is_empty() {
+                        ParseErrorKind::EmptyItem
+                    } else {
+                        ParseErrorKind::MissingValue
+                    },
+                });
+            };
+            let range = parse_range::<Idx, _>(offset, range)?;
+            let (offset, value) = trim(offset + item.len() - value.len(), value);
+            let value = unescape(value).parse().map_err(|err| ParseError {
+                position: offset,
+                kind: ParseErrorKind::InvalidValue(err),
+            })?;
+            map.insert_range(range, value);
+        }
+        Ok(map)
+    }
+}
+
+/// Writes the map as comma separated `range=value` items, escaping `,`, `=` and `\` in values.
+impl<Idx: OrderedIndex + fmt::Display, V: fmt::Display> fmt::Display for InversionMap<Idx, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut separator = "";
+        for (range, value) in self {
+            f.write_str(separator)?;
+            write_range(f, range)?;
+            f.write_char('=')?;
+            write!(Escaped(f), "{}", value)?;
+            separator = ",";
+        }
+        Ok(())
+    }
+}
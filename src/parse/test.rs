@@ -0,0 +1,109 @@
+use super::*;
+
+use alloc::string::{String, ToString};
+use core::num::IntErrorKind;
+
+use crate::list::SplitInversionList;
+
+fn il(ranges: &[RangeInclusive<u8>]) -> InversionList<u8> {
+    ranges.iter().cloned().collect()
+}
+
+#[test]
+fn parse_list() {
+    assert_eq!("".parse::<InversionList<u8>>(), Ok(il(&[])));
+    assert_eq!(
+        "1-5, 7 ,9 - 12,100-".parse::<InversionList<u8>>(),
+        Ok(il(&[1..=5, 7..=7, 9..=12, 100..=255]))
+    );
+    // overlapping and adjacent items are merged
+    assert_eq!(
+        "9-12,1-5,3-8".parse::<InversionList<u8>>(),
+        Ok(il(&[1..=12]))
+    );
+    assert_eq!(
+        "-10--5,-1".parse::<InversionList<i8>>(),
+        Ok([-10..=-5, -1..=-1].into_iter().collect())
+    );
+}
+
+#[test]
+fn parse_list_errors() {
+    let err = |s: &str| s.parse::<InversionList<u8>>().unwrap_err();
+    let e = err("1-5,,7");
+    assert_eq!((e.position, e.kind), (4, ParseErrorKind::EmptyItem));
+    let e = err("1,5-3");
+    assert_eq!((e.position, e.kind), (2, ParseErrorKind::ReversedRange));
+    let e = err("1, 2-300");
+    assert_eq!(e.position, 5);
+    assert!(matches!(
+        e.kind,
+        ParseErrorKind::InvalidIndex(ref e) if *e.kind() == IntErrorKind::PosOverflow
+    ));
+    let e = err("1-2-3");
+    assert_eq!(e.position, 2);
+    assert!(matches!(e.kind, ParseErrorKind::InvalidIndex(_)));
+    assert_eq!(
+        err("x").to_string(),
+        "invalid index: invalid digit found in string at position 0"
+    );
+}
+
+#[test]
+fn display_list() {
+    assert_eq!(il(&[]).to_string(), "");
+    assert_eq!(
+        il(&[1..=5, 7..=7, 9..=12, 100..=255]).to_string(),
+        "1-5,7,9-12,100-"
+    );
+    let mut split = SplitInversionList::from(il(&[0..=9, 20..=30]));
+    split.split(5);
+    assert_eq!(split.collapse().to_string(), "0-9,20-30");
+    assert_eq!(il(&[255..=255]).to_string(), "255");
+}
+
+#[test]
+fn list_round_trip() {
+    for s in ["", "0-", "0,2,4-6,254-", "17-99,101"] {
+        let list = s.parse::<InversionList<u8>>().unwrap();
+        assert_eq!(list.to_string(), s);
+        assert_eq!(list.to_string().parse(), Ok(list));
+    }
+}
+
+#[test]
+fn map() {
+    let map = "1-5=a, 7=b,3-4=c,200-=d"
+        .parse::<InversionMap<u8, String>>()
+        .unwrap();
+    assert_eq!(map.to_string(), "1-2=a,3-4=c,5=a,7=b,200-=d");
+    assert_eq!(map.to_string().parse(), Ok(map));
+
+    let err = |s: &str| s.parse::<InversionMap<u8, u8>>().unwrap_err();
+    let e = err("1-5=1,7");
+    assert_eq!((e.position, e.kind), (7, ParseErrorKind::MissingValue));
+    let e = err("1-5=1, 7= x");
+    assert_eq!(e.position, 10);
+    assert!(matches!(e.kind, ParseErrorKind::InvalidValue(_)));
+    let e = err("1=1,,");
+    assert_eq!((e.position, e.kind), (4, ParseErrorKind::EmptyItem));
+}
+
+#[test]
+fn map_round_trip() {
+    let mut map = InversionMap::<u8, String>::new();
+    map.insert_range(0..=0, "a,b".into());
+    map.insert_range(3..5, "x=y".into());
+    map.insert_range(9..=9, r"back\slash".into());
+    map.insert_range(20.., "plain".into());
+    let s = map.to_string();
+    assert_eq!(s, r"0=a\,b,3-4=x\=y,9=back\\slash,20-=plain");
+    assert_eq!(s.parse(), Ok(map));
+
+    // escaping works no matter which characters the escaped ones sit next to
+    for value in [",", "=", "\\", ",,==\\\\", "a\\,b=c"] {
+        let mut map = InversionMap::<u8, String>::new();
+        map.insert_range(1..=2, value.into());
+        assert_eq!(map.to_string().parse(), Ok(map), "{value}");
+    }
+}
@@ -0,0 +1,540 @@
+//! A persistent inversion map whose versions share their unchanged structure.
+
+use core::fmt;
+use core::iter::{FromIterator, FusedIterator};
+use core::ops::{RangeBounds, RangeInclusive};
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::map::Entry;
+use crate::util::{bounds_to_range, predecessor, successor, xorshift};
+use crate::{InversionMap, OrderedIndex};
+
+#[cfg(test)]
+mod test;
+
+type Tree<Idx, V> = Option<Arc<Node<Idx, V>>>;
+
+// A treap node, ordered by `entry.start` and heap ordered by `priority`.
+#[derive(Clone)]
+struct Node<Idx, V> {
+    entry: Entry<Idx, V>,
+    priority: u32,
+    len: usize,
+    left: Tree<Idx, V>,
+    right: Tree<Idx, V>,
+}
+
+/// An immutable inversion map where every edit returns a new map.
+///
+/// The ranges are kept in a balanced tree of reference counted nodes, an edit only copies the
+/// `O(log n)` nodes on the paths to the ranges it touches and shares everything else with the map
+/// it was made from. Cloning a map is `O(1)`.
+pub struct PersistentInversionMap<Idx, V> {
+    root: Tree<Idx, V>,
+    // state of the xorshift generator handing out node priorities
+    seed: u32,
+}
+
+impl<Idx, V> Clone for PersistentInversionMap<Idx, V> {
+    fn clone(&self) -> Self {
+        PersistentInversionMap {
+            root: self.root.clone(),
+            seed: self.seed,
+        }
+    }
+}
+
+impl<Idx, V> Default for PersistentInversionMap<Idx, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Idx: OrderedIndex, V: fmt::Debug> fmt::Debug for PersistentInversionMap<Idx, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<Idx: OrderedIndex, V: PartialEq> PartialEq for PersistentInversionMap<Idx, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<Idx: OrderedIndex, V: Eq> Eq for PersistentInversionMap<Idx, V> {}
+
+impl<Idx, V> PersistentInversionMap<Idx, V> {
+    pub const fn new() -> Self {
+        PersistentInversionMap {
+            root: None,
+            seed: 0x9E37_79B9,
+        }
+    }
+}
+
+// region: delegate methods
+impl<Idx: OrderedIndex, V> PersistentInversionMap<Idx, V> {
+    #[inline]
+    pub fn len(&self) -> usize {
+        len(&self.root)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    #[inline]
+    pub fn start(&self) -> Option<Idx> {
+        self.first().map(|(range, _)| *range.start())
+    }
+
+    /// Returns the last index covered by the map, this is an inclusive bound.
+    #[inline]
+    pub fn end(&self) -> Option<Idx> {
+        self.last().map(|(range, _)| *range.end())
+    }
+
+    pub fn first(&self) -> Option<(RangeInclusive<Idx>, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = node.left.as_deref() {
+            node = left;
+        }
+        Some((node.entry.range(), &node.entry.value))
+    }
+
+    pub fn last(&self) -> Option<(RangeInclusive<Idx>, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = node.right.as_deref() {
+            node = right;
+        }
+        Some((node.entry.range(), &node.entry.value))
+    }
+}
+// endregion
+
+impl<Idx: OrderedIndex, V> PersistentInversionMap<Idx, V> {
+    /// Checks whether the given index is contained in the map.
+    pub fn contains(&self, index: Idx) -> bool {
+        self.lookup(index).is_some()
+    }
+
+    /// Checks whether a single range of the map fully contains the given range.
+    ///
+    /// Empty ranges are never contained.
+    pub fn contains_range<R: RangeBounds<Idx>>(&self, range: R) -> bool {
+        bounds_to_range(range).is_some_and(|range| {
+            self.lookup(*range.start())
+                .is_some_and(|(it, _)| it.end() >= range.end())
+        })
+    }
+
+    /// Looks up the range and value associated with the given index.
+    pub fn lookup(&self, index: Idx) -> Option<(RangeInclusive<Idx>, &V)> {
+        let mut tree = &self.root;
+        while let Some(node) = tree.as_deref() {
+            tree = if index < node.entry.start {
+                &node.left
+            } else if node.entry.end < index {
+                &node.right
+            } else {
+                return Some((node.entry.range(), &node.entry.value));
+            };
+        }
+        None
+    }
+
+    /// Looks up all entries whose ranges overlap with the given range.
+    pub fn lookup_range<R: RangeBounds<Idx>>(&self, range: R) -> Option<Iter<'_, Idx, V>> {
+        let (start, end) = bounds_to_range(range)?.into_inner();
+        let mut iter = Iter {
+            front: Vec::new(),
+            back: Vec::new(),
+            len: 0,
+        };
+        // push the path to the first entry ending at or after `start`, counting those entries
+        let mut tree = &self.root;
+        while let Some(node) = tree.as_deref() {
+            tree = if start <= node.entry.end {
+                iter.front.push(node);
+                iter.len += 1 + len(&node.right);
+                &node.left
+            } else {
+                &node.right
+            };
+        }
+        // push the path to the last entry starting at or before `end`, leaving out the count of
+        // the entries after it
+        let mut tree = &self.root;
+        while let Some(node) = tree.as_deref() {
+            tree = if end < node.entry.start {
+                iter.len -= 1 + len(&node.right);
+                &node.left
+            } else {
+                iter.back.push(node);
+                &node.right
+            };
+        }
+        (iter.len > 0).then_some(iter)
+    }
+
+    /// Check if the given range intersects with any ranges inside of the map.
+    pub fn intersects<R: RangeBounds<Idx>>(&self, range: R) -> bool {
+        self.lookup_range(range).is_some()
+    }
+
+    /// Returns the complete surrounding range, if any.
+    pub fn span(&self) -> Option<RangeInclusive<Idx>> {
+        Some(self.start()?..=self.end()?)
+    }
+
+    /// An iterator over the ranges and values contained in this map.
+    pub fn iter(&self) -> Iter<'_, Idx, V> {
+        self.lookup_range(..).unwrap_or(Iter {
+            front: Vec::new(),
+            back: Vec::new(),
+            len: 0,
+        })
+    }
+}
+
+impl<Idx: OrderedIndex, V: Clone> PersistentInversionMap<Idx, V> {
+    /// Returns a new map with the given range inserted, overwriting any ranges that are contained
+    /// within. Ranges that partially overlap will be shortened or split accordingly.
+    pub fn insert_range<R: RangeBounds<Idx>>(&self, range: R, value: V) -> Self {
+        let Some(range) = bounds_to_range(range) else {
+            return self.clone();
+        };
+        let (start, end) = range.into_inner();
+        let mut edit = self.edit(start, end);
+
+        let mut right = None;
+        if let Some(left) = edit.left.as_mut() {
+            if end < left.end {
+                right = Some((left.end, left.value.clone()));
+            }
+            // `left.start < start` so this can't underflow
+            left.end = predecessor(start).unwrap();
+        }
+        if let Some(last) = edit.last.take() {
+            if end < last.end {
+                right = Some((last.end, last.value));
+            }
+        }
+        edit.entries.push(Entry { start, end, value });
+        if let Some((right_end, value)) = right {
+            edit.entries.push(Entry {
+                // `end < right_end` so this can't overflow
+                start: successor(end).unwrap(),
+                end: right_end,
+                value,
+            });
+        }
+        edit.finish()
+    }
+
+    /// Returns a new map with the given range added, ranges overlapping it are merged into it.
+    pub fn add_range<R: RangeBounds<Idx>>(&self, range: R, value: V) -> Self {
+        let Some(range) = bounds_to_range(range) else {
+            return self.clone();
+        };
+        let (mut start, mut end) = range.into_inner();
+        let mut edit = self.edit(start, end);
+
+        if let Some(left) = edit.left.take() {
+            start = left.start;
+            end = end.max(left.end);
+        }
+        if let Some(last) = edit.last.take() {
+            end = end.max(last.end);
+        }
+        edit.entries.push(Entry { start, end, value });
+        edit.finish()
+    }
+
+    /// Returns a new map with the given range removed. Ranges that partially overlap are
+    /// shortened, with their remaining values produced by the split functions.
+    pub fn remove_range<R: RangeBounds<Idx>>(
+        &self,
+        range: R,
+        split_boundary_left: impl FnOnce(RangeInclusive<Idx>, &V) -> V,
+        split_boundary_right: impl FnOnce(RangeInclusive<Idx>, &V) -> V,
+    ) -> Self {
+        let Some(range) = bounds_to_range(range) else {
+            return self.clone();
+        };
+        let (start, end) = range.into_inner();
+        let mut edit = self.edit(start, end);
+
+        // if the left range reaches past `end` it is the only one overlapping
+        let last = match edit.left.as_ref() {
+            Some(left) if end < left.end => Some(left.clone()),
+            _ => edit.last.take(),
+        };
+        if let Some(left) = edit.left.as_mut() {
+            left.value = split_boundary_left(left.range(), &left.value);
+            // `left.start < start` so this can't underflow
+            left.end = predecessor(start).unwrap();
+        }
+        if let Some(last) = last.filter(|last| end < last.end) {
+            edit.entries.push(Entry {
+                // `end < last.end` so this can't overflow
+                start: successor(end).unwrap(),
+                end: last.end,
+                value: split_boundary_right(last.range(), &last.value),
+            });
+        }
+        edit.finish()
+    }
+
+    /// Returns a new map where the range containing `at` is split in two, the right part
+    /// starting at `at`.
+    ///
+    /// If no range contains `at`, or a range already starts at `at`, the map is returned as is.
+    pub fn split(&self, at: Idx) -> Self {
+        match self.lookup(at) {
+            Some((range, _)) if *range.start() < at => {}
+            _ => return self.clone(),
+        }
+        let mut edit = self.edit(at, at);
+        let mut left = edit.left.take().unwrap();
+        let right = Entry {
+            start: at,
+            end: left.end,
+            value: left.value.clone(),
+        };
+        left.end = predecessor(at).unwrap();
+        edit.entries.push(left);
+        edit.entries.push(right);
+        edit.finish()
+    }
+
+    /// Cuts the tree apart around `start..=end`, taking out the range overlapping `start` from
+    /// the left and the last range starting within.
+    fn edit(&self, start: Idx, end: Idx) -> Edit<Idx, V> {
+        let (before, rest) = split(self.root.clone(), start);
+        let (within, after) = match successor(end) {
+            Some(end) => split(rest, end),
+            None => (rest, None),
+        };
+        let mut last = before.as_deref();
+        while let Some(right) = last.and_then(|node| node.right.as_deref()) {
+            last = Some(right);
+        }
+        let (before, left) = match last {
+            Some(node) if start <= node.entry.end => pop_last(before),
+            _ => (before, None),
+        };
+        let (_, last) = pop_last(within);
+        Edit {
+            before,
+            after,
+            left,
+            last,
+            entries: Vec::new(),
+            seed: self.seed,
+        }
+    }
+}
+
+// The pieces of a tree cut apart for an edit, the overlapping ranges between `before` and `after`
+// are dropped unless pushed back into `entries`.
+struct Edit<Idx, V> {
+    before: Tree<Idx, V>,
+    after: Tree<Idx, V>,
+    // the range starting in front of the edited range while overlapping it
+    left: Option<Entry<Idx, V>>,
+    // the last range starting inside of the edited range
+    last: Option<Entry<Idx, V>>,
+    entries: Vec<Entry<Idx, V>>,
+    seed: u32,
+}
+
+impl<Idx: OrderedIndex, V: Clone> Edit<Idx, V> {
+    fn finish(self) -> PersistentInversionMap<Idx, V> {
+        let Edit {
+            before,
+            after,
+            left,
+            entries,
+            mut seed,
+            ..
+        } = self;
+        let mut root = before;
+        for entry in left.into_iter().chain(entries) {
+            root = merge(root, Some(leaf(entry, xorshift(&mut seed))));
+        }
+        PersistentInversionMap {
+            root: merge(root, after),
+            seed,
+        }
+    }
+}
+
+fn len<Idx, V>(tree: &Tree<Idx, V>) -> usize {
+    tree.as_ref().map_or(0, |node| node.len)
+}
+
+fn leaf<Idx, V>(entry: Entry<Idx, V>, priority: u32) -> Arc<Node<Idx, V>> {
+    Arc::new(Node {
+        entry,
+        priority,
+        len: 1,
+        left: None,
+        right: None,
+    })
+}
+
+// Takes the node out of its `Arc`, copying it only if it is shared with another version.
+fn unshare<Idx: Clone, V: Clone>(node: Arc<Node<Idx, V>>) -> Node<Idx, V> {
+    Arc::try_unwrap(node).unwrap_or_else(|node| (*node).clone())
+}
+
+fn rejoin<Idx, V>(mut node: Node<Idx, V>) -> Tree<Idx, V> {
+    node.len = len(&node.left) + 1 + len(&node.right);
+    Some(Arc::new(node))
+}
+
+/// Splits the tree into the ranges starting before `key` and the ones starting at or after it.
+fn split<Idx: OrderedIndex, V: Clone>(
+    tree: Tree<Idx, V>,
+    key: Idx,
+) -> (Tree<Idx, V>, Tree<Idx, V>) {
+    let Some(node) = tree else {
+        return (None, None);
+    };
+    let mut node = unshare(node);
+    if node.entry.start < key {
+        let (left, right) = split(node.right.take(), key);
+        node.right = left;
+        (rejoin(node), right)
+    } else {
+        let (left, right) = split(node.left.take(), key);
+        node.left = right;
+        (left, rejoin(node))
+    }
+}
+
+/// Joins two trees, all ranges of `left` have to come before the ranges of `right`.
+fn merge<Idx: OrderedIndex, V: Clone>(left: Tree<Idx, V>, right: Tree<Idx, V>) -> Tree<Idx, V> {
+    match (left, right) {
+        (None, tree) | (tree, None) => tree,
+        (Some(left), Some(right)) if left.priority >= right.priority => {
+            let mut left = unshare(left);
+            left.right = merge(left.right.take(), Some(right));
+            rejoin(left)
+        }
+        (left, Some(right)) => {
+            let mut right = unshare(right);
+            right.left = merge(left, right.left.take());
+            rejoin(right)
+        }
+    }
+}
+
+fn pop_last<Idx: OrderedIndex, V: Clone>(
+    tree: Tree<Idx, V>,
+) -> (Tree<Idx, V>, Option<Entry<Idx, V>>) {
+    let Some(node) = tree else {
+        return (None, None);
+    };
+    let mut node = unshare(node);
+    match node.right.take() {
+        Some(right) => {
+            let (right, last) = pop_last(Some(right));
+            node.right = right;
+            (rejoin(node), last)
+        }
+        None => (node.left, Some(node.entry)),
+    }
+}
+
+impl<Idx: OrderedIndex, V: Clone> From<&InversionMap<Idx, V>> for PersistentInversionMap<Idx, V> {
+    fn from(map: &InversionMap<Idx, V>) -> Self {
+        map.iter()
+            .map(|(range, value)| (range, value.clone()))
+            .collect()
+    }
+}
+
+impl<Idx: OrderedIndex, V: Clone> From<&PersistentInversionMap<Idx, V>> for InversionMap<Idx, V> {
+    fn from(map: &PersistentInversionMap<Idx, V>) -> Self {
+        InversionMap {
+            ranges: map
+                .iter()
+                .map(|(range, value)| Entry {
+                    start: *range.start(),
+                    end: *range.end(),
+                    value: value.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<Idx: OrderedIndex, V: Clone> FromIterator<(RangeInclusive<Idx>, V)>
+    for PersistentInversionMap<Idx, V>
+{
+    fn from_iter<T: IntoIterator<Item = (RangeInclusive<Idx>, V)>>(iter: T) -> Self {
+        iter.into_iter().fold(Self::new(), |map, (range, value)| {
+            map.insert_range(range, value)
+        })
+    }
+}
+
+impl<'im, Idx: OrderedIndex, V> IntoIterator for &'im PersistentInversionMap<Idx, V> {
+    type Item = (RangeInclusive<Idx>, &'im V);
+    type IntoIter = Iter<'im, Idx, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An in-order iterator over the entries of a [`PersistentInversionMap`].
+pub struct Iter<'im, Idx, V> {
+    // the nodes whose entry and right subtree are still to be visited, the next one on top
+    front: Vec<&'im Node<Idx, V>>,
+    // the nodes whose entry and left subtree are still to be visited, the next one on top
+    back: Vec<&'im Node<Idx, V>>,
+    // the number of entries left, both stacks reach past the ones yielded from the other end
+    len: usize,
+}
+
+impl<'im, Idx: OrderedIndex, V> Iterator for Iter<'im, Idx, V> {
+    type Item = (RangeInclusive<Idx>, &'im V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.len = self.len.checked_sub(1)?;
+        let node = self.front.pop()?;
+        let mut tree = node.right.as_deref();
+        while let Some(next) = tree {
+            self.front.push(next);
+            tree = next.left.as_deref();
+        }
+        Some((node.entry.range(), &node.entry.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<Idx: OrderedIndex, V> DoubleEndedIterator for Iter<'_, Idx, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.len = self.len.checked_sub(1)?;
+        let node = self.back.pop()?;
+        let mut tree = node.left.as_deref();
+        while let Some(next) = tree {
+            self.back.push(next);
+            tree = next.right.as_deref();
+        }
+        Some((node.entry.range(), &node.entry.value))
+    }
+}
+
+impl<Idx: OrderedIndex, V> ExactSizeIterator for Iter<'_, Idx, V> {}
+impl<Idx: OrderedIndex, V> FusedIterator for Iter<'_, Idx, V> {}
@@ -0,0 +1,138 @@
+use super::*;
+use crate::testing::{random_range, xorshift};
+
+fn to_vec<Idx: OrderedIndex, V: Clone>(
+    map: &PersistentInversionMap<Idx, V>,
+) -> Vec<(RangeInclusive<Idx>, V)> {
+    map.iter().map(|(range, v)| (range, v.clone())).collect()
+}
+
+#[test]
+fn insert_range_splits() {
+    let pm = PersistentInversionMap::new().insert_range(0..10, 0);
+    let pm2 = pm.insert_range(3..5, 1);
+    assert_eq!(to_vec(&pm), [(0..=9, 0)]);
+    assert_eq!(to_vec(&pm2), [(0..=2, 0), (3..=4, 1), (5..=9, 0)]);
+    let pm3 = pm2.insert_range(2..=6, 2);
+    assert_eq!(to_vec(&pm3), [(0..=1, 0), (2..=6, 2), (7..=9, 0)]);
+    assert_eq!(to_vec(&pm2), [(0..=2, 0), (3..=4, 1), (5..=9, 0)]);
+}
+
+#[test]
+fn add_range_merges() {
+    let pm = PersistentInversionMap::new()
+        .add_range(0..5, 0)
+        .add_range(10..15, 1)
+        .add_range(20..25, 2);
+    assert_eq!(to_vec(&pm.add_range(3..=20, 3)), [(0..=24, 3)]);
+    assert_eq!(pm.len(), 3);
+}
+
+#[test]
+fn lookup_range() {
+    let pm: PersistentInversionMap<u32, u32> = [(0..=4, 0), (10..=14, 1), (20..=24, 2)]
+        .into_iter()
+        .collect();
+    assert!(pm.lookup_range(5..10).is_none());
+    assert_eq!(
+        pm.lookup_range(4..=20)
+            .unwrap()
+            .map(|(r, &v)| (r, v))
+            .collect::<Vec<_>>(),
+        [(0..=4, 0), (10..=14, 1), (20..=24, 2)]
+    );
+    assert_eq!(
+        pm.lookup_range(12..=13)
+            .unwrap()
+            .map(|(r, &v)| (r, v))
+            .collect::<Vec<_>>(),
+        [(10..=14, 1)]
+    );
+    assert_eq!(pm.lookup(12), Some((10..=14, &1)));
+    assert_eq!(pm.lookup(15), None);
+    assert!(pm.contains_range(10..15));
+    assert!(!pm.contains_range(10..16));
+    assert_eq!(pm.span(), Some(0..=24));
+}
+
+#[test]
+fn iter_rev() {
+    let pm: PersistentInversionMap<u32, u32> = (0..10).map(|i| (i * 10..=i * 10 + 4, i)).collect();
+    assert!(pm.iter().rev().map(|(_, &v)| v).eq((0..10).rev()));
+    assert!(pm
+        .lookup_range(14..=52)
+        .unwrap()
+        .rev()
+        .map(|(_, &v)| v)
+        .eq([5, 4, 3, 2, 1]));
+    // both ends meet without yielding an entry twice
+    let mut iter = pm.lookup_range(14..=52).unwrap();
+    assert_eq!(iter.len(), 5);
+    assert_eq!(iter.next().map(|(_, &v)| v), Some(1));
+    assert_eq!(iter.next_back().map(|(_, &v)| v), Some(5));
+    assert_eq!(iter.next_back().map(|(_, &v)| v), Some(4));
+    assert_eq!(iter.next().map(|(_, &v)| v), Some(2));
+    assert_eq!(iter.next_back().map(|(_, &v)| v), Some(3));
+    assert!(iter.next().is_none());
+    assert!(iter.next_back().is_none());
+}
+
+#[test]
+fn split_max_value() {
+    let pm = PersistentInversionMap::new().insert_range(0..=u8::MAX, 0);
+    let pm = pm.split(u8::MAX).split(0);
+    assert_eq!(to_vec(&pm), [(0..=254, 0), (255..=255, 0)]);
+}
+
+// Runs random edits against both maps, keeping every persistent version around to check that
+// later edits leave it untouched.
+#[test]
+fn matches_inversion_map() {
+    let mut seed = 0x85eb_ca6b;
+    let mut versions = alloc::vec![(
+        PersistentInversionMap::<u8, u32>::new(),
+        InversionMap::new()
+    )];
+    for step in 0..2000 {
+        let (pm, mut im) = versions[xorshift(&mut seed) as usize % versions.len()].clone();
+        let (start, end) = random_range(&mut seed, 40).into_inner();
+        let pm = match xorshift(&mut seed) % 4 {
+            0 => {
+                im.insert_range(start..=end, step);
+                pm.insert_range(start..=end, step)
+            }
+            1 => {
+                im.add_range(start..=end, step);
+                pm.add_range(start..=end, step)
+            }
+            2 => {
+                let left = |r: RangeInclusive<u8>, &v: &u32| v ^ *r.start() as u32;
+                let right = |r: RangeInclusive<u8>, &v: &u32| v ^ ((*r.end() as u32) << 8);
+                im.remove_range(start..=end, left, right);
+                pm.remove_range(start..=end, left, right)
+            }
+            _ => {
+                im.split(start);
+                pm.split(start)
+            }
+        };
+        assert_eq!(InversionMap::from(&pm), im);
+        assert_eq!(pm.len(), im.len());
+        assert_eq!(pm.lookup(end), im.lookup(end));
+        assert_eq!(
+            pm.lookup_range(start..=end).map_or(0, Iterator::count),
+            im.lookup_range(start..=end).map_or(0, |e| e.len())
+        );
+        if let Some(entries) = im.lookup_range(start..=end) {
+            assert!(pm
+                .lookup_range(start..=end)
+                .unwrap()
+                .rev()
+                .eq(entries.iter().rev()));
+        }
+        versions.push((pm, im));
+    }
+    for (pm, im) in &versions {
+        assert_eq!(InversionMap::from(pm), *im);
+    }
+}
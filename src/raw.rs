@@ -0,0 +1,421 @@
+//! The sorted entry representation shared by the slice backed maps, and the search and mutation
+//! algorithms operating on it.
+
+use core::cmp::Ordering;
+use core::iter::{once, FusedIterator};
+use core::ops::{Range, RangeInclusive};
+
+use crate::util::{predecessor, range_count, successor};
+use crate::OrderedIndex;
+
+use Err as Insert;
+use Ok as Within;
+
+/// A single range of a map, `start` and `end` are both inclusive so that ranges touching
+/// [`OrderedIndex::max_value()`] can be represented.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Entry<Idx, V> {
+    pub(crate) start: Idx,
+    pub(crate) end: Idx,
+    pub(crate) value: V,
+}
+
+impl<Idx: OrderedIndex, V> Entry<Idx, V> {
+    #[inline]
+    pub(crate) fn range(&self) -> RangeInclusive<Idx> {
+        self.start..=self.end
+    }
+}
+
+impl<Idx, V> From<Entry<Idx, V>> for (RangeInclusive<Idx>, V) {
+    fn from(Entry { start, end, value }: Entry<Idx, V>) -> Self {
+        (start..=end, value)
+    }
+}
+
+// These are public APIs that abstract away the internal representation of the inversion map.
+
+pub struct EntriesRef<'im, Idx, V> {
+    pub(crate) slice: &'im [Entry<Idx, V>],
+}
+
+impl<'im, Idx: OrderedIndex, V> EntriesRef<'im, Idx, V> {
+    pub const fn is_empty(&self) -> bool {
+        self.slice.is_empty()
+    }
+
+    pub const fn len(&self) -> usize {
+        self.slice.len()
+    }
+
+    pub fn iter(&self) -> Iter<'_, Idx, V> {
+        Iter::new(self.slice)
+    }
+}
+
+/// An iterator over the entries of a map.
+#[derive(Debug)]
+pub struct Iter<'il, Idx: OrderedIndex, V> {
+    iter: core::slice::Iter<'il, Entry<Idx, V>>,
+}
+
+impl<'il, Idx: OrderedIndex, V> Iter<'il, Idx, V> {
+    pub(crate) fn new(entries: &'il [Entry<Idx, V>]) -> Self {
+        Iter {
+            iter: entries.iter(),
+        }
+    }
+}
+
+impl<'il, Idx: OrderedIndex, V> Clone for Iter<'il, Idx, V> {
+    fn clone(&self) -> Self {
+        Self {
+            iter: self.iter.clone(),
+        }
+    }
+}
+
+impl<'a, Idx: OrderedIndex, V> Iterator for Iter<'a, Idx, V> {
+    type Item = (RangeInclusive<Idx>, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|entry| (entry.range(), &entry.value))
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<Idx: OrderedIndex, V> DoubleEndedIterator for Iter<'_, Idx, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next_back()
+            .map(|entry| (entry.range(), &entry.value))
+    }
+}
+
+impl<Idx: OrderedIndex, V> FusedIterator for Iter<'_, Idx, V> {}
+impl<Idx: OrderedIndex, V> ExactSizeIterator for Iter<'_, Idx, V> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+/// The backing buffer of a map.
+///
+/// Mutations first [`reserve`](Storage::reserve) the room they need, so that a failing
+/// reservation leaves the map untouched.
+pub(crate) trait Storage<Idx, V> {
+    type Error;
+
+    fn entries(&self) -> &[Entry<Idx, V>];
+
+    fn entries_mut(&mut self) -> &mut [Entry<Idx, V>];
+
+    /// Makes sure that `removed` entries can be replaced by `inserted` ones.
+    fn reserve(&mut self, removed: usize, inserted: usize) -> Result<(), Self::Error>;
+
+    /// Replaces the entries in `range` with `with`, the room for which has to be reserved upfront.
+    fn splice(&mut self, range: Range<usize>, with: impl IntoIterator<Item = Entry<Idx, V>>);
+}
+
+#[cfg(feature = "alloc")]
+impl<Idx, V> Storage<Idx, V> for alloc::vec::Vec<Entry<Idx, V>> {
+    type Error = core::convert::Infallible;
+
+    #[inline]
+    fn entries(&self) -> &[Entry<Idx, V>] {
+        self
+    }
+
+    #[inline]
+    fn entries_mut(&mut self) -> &mut [Entry<Idx, V>] {
+        self
+    }
+
+    #[inline]
+    fn reserve(&mut self, removed: usize, inserted: usize) -> Result<(), Self::Error> {
+        self.reserve(inserted.saturating_sub(removed));
+        Ok(())
+    }
+
+    #[inline]
+    fn splice(&mut self, range: Range<usize>, with: impl IntoIterator<Item = Entry<Idx, V>>) {
+        _ = self.splice(range, with);
+    }
+}
+
+// region: search
+
+fn bin_search_ordering<Idx: OrderedIndex, V>(entry: &Entry<Idx, V>, key: Idx) -> Ordering {
+    if key < entry.start {
+        Ordering::Greater
+    } else if entry.end < key {
+        Ordering::Less
+    } else {
+        Ordering::Equal
+    }
+}
+
+pub(crate) fn binary_search<Idx: OrderedIndex, V>(
+    entries: &[Entry<Idx, V>],
+    key: Idx,
+) -> Result<usize, usize> {
+    entries.binary_search_by(move |entry| bin_search_ordering(entry, key))
+}
+
+pub(crate) fn range_binary_search<Idx: OrderedIndex, V>(
+    entries: &[Entry<Idx, V>],
+    range: RangeInclusive<Idx>,
+) -> (Result<usize, usize>, Result<usize, usize>) {
+    let (start, end) = range.into_inner();
+    let start @ (Within(idx) | Insert(idx)) = binary_search(entries, start);
+    let end = binary_search(&entries[idx..], end);
+
+    (
+        start,
+        match end {
+            Within(e) => Within(idx + e),
+            Insert(e) => Insert(idx + e),
+        },
+    )
+}
+
+/// Returns the indices of all entries overlapping the given range.
+pub(crate) fn overlapping<Idx: OrderedIndex, V>(
+    entries: &[Entry<Idx, V>],
+    range: RangeInclusive<Idx>,
+) -> Range<usize> {
+    match range_binary_search(entries, range) {
+        (Within(s) | Insert(s), Within(e)) => s..e + 1,
+        (Within(s) | Insert(s), Insert(e)) => s..e,
+    }
+}
+
+/// Checks whether a single entry fully contains the given range.
+pub(crate) fn contains_range<Idx: OrderedIndex, V>(
+    entries: &[Entry<Idx, V>],
+    range: RangeInclusive<Idx>,
+) -> bool {
+    match binary_search(entries, *range.start()) {
+        Within(idx) => entries[idx].end >= *range.end(),
+        Insert(_) => false,
+    }
+}
+
+/// Returns the number of indices covered by the entries, saturating at `u128::MAX`.
+pub(crate) fn count<Idx: OrderedIndex, V>(entries: &[Entry<Idx, V>]) -> u128 {
+    entries.iter().fold(0, |acc, entry| {
+        acc.saturating_add(range_count(&entry.range()))
+    })
+}
+
+pub(crate) fn intersects<Idx: OrderedIndex, V>(
+    entries: &[Entry<Idx, V>],
+    range: RangeInclusive<Idx>,
+) -> bool {
+    match range_binary_search(entries, range) {
+        // check if there is at least one range inside of our range
+        (Insert(idx_s), Insert(idx_e)) => idx_e - idx_s > 0,
+        _ => true,
+    }
+}
+
+// endregion
+
+// region: mutation
+
+/// Inserts a unit entry at `index` if there is none, returning whether that was the case.
+/// Otherwise `within` is invoked with the index of the entry containing `index`.
+pub(crate) fn unit_with<Idx: OrderedIndex, V, S: Storage<Idx, V>>(
+    storage: &mut S,
+    index: Idx,
+    value: V,
+    within: impl FnOnce(&mut S, usize, V) -> Result<(), S::Error>,
+) -> Result<bool, S::Error> {
+    match binary_search(storage.entries(), index) {
+        Insert(insert_idx) => {
+            storage.reserve(0, 1)?;
+            storage.splice(
+                insert_idx..insert_idx,
+                once(Entry {
+                    start: index,
+                    end: index,
+                    value,
+                }),
+            );
+            Ok(true)
+        }
+        Within(idx) => within(storage, idx, value).map(|()| false),
+    }
+}
+
+pub(crate) fn insert_range_with<Idx: OrderedIndex, V: Clone, S: Storage<Idx, V>>(
+    storage: &mut S,
+    range: RangeInclusive<Idx>,
+    value: impl FnOnce(EntriesRef<'_, Idx, V>) -> V,
+) -> Result<(), S::Error> {
+    let (start, end) = range.clone().into_inner();
+    let Range {
+        start: idx_s,
+        end: idx_e,
+    } = overlapping(storage.entries(), range);
+    let (trim_left, trim_right) = match &storage.entries()[idx_s..idx_e] {
+        [] => (false, false),
+        [first, .., last] | [first @ last] => (first.start < start, end < last.end),
+    };
+    // the new range lies strictly inside of a single range, so that one gets split in two
+    let split = idx_e - idx_s == 1 && trim_left && trim_right;
+    if split {
+        storage.reserve(0, 2)?;
+    } else {
+        storage.reserve(idx_e - idx_s - trim_left as usize - trim_right as usize, 1)?;
+    }
+
+    let slice = &storage.entries()[idx_s..idx_e];
+    let value = value(EntriesRef { slice });
+    let entry = Entry { start, end, value };
+    if split {
+        let it = &storage.entries()[idx_s];
+        let right = Entry {
+            // `end < it.end` so this can't overflow
+            start: successor(end).unwrap(),
+            end: it.end,
+            value: it.value.clone(),
+        };
+        // `it.start < start` so this can't underflow
+        storage.entries_mut()[idx_s].end = predecessor(start).unwrap();
+        storage.splice(idx_s + 1..idx_s + 1, [entry, right]);
+    } else {
+        let mut replace = idx_s..idx_e;
+        if trim_left {
+            storage.entries_mut()[idx_s].end = predecessor(start).unwrap();
+            replace.start += 1;
+        }
+        if trim_right {
+            storage.entries_mut()[idx_e - 1].start = successor(end).unwrap();
+            replace.end -= 1;
+        }
+        storage.splice(replace, once(entry));
+    }
+    Ok(())
+}
+
+pub(crate) fn add_range_with<Idx: OrderedIndex, V, S: Storage<Idx, V>>(
+    storage: &mut S,
+    range: RangeInclusive<Idx>,
+    value: impl FnOnce(EntriesRef<'_, Idx, V>) -> V,
+) -> Result<(), S::Error> {
+    let (start, end) = range.clone().into_inner();
+    let Range {
+        start: idx_s,
+        end: idx_e,
+    } = overlapping(storage.entries(), range);
+    storage.reserve(idx_e - idx_s, 1)?;
+
+    let slice = &storage.entries()[idx_s..idx_e];
+    let value = value(EntriesRef { slice });
+    match slice {
+        // Surround nothing so insert
+        [] => storage.splice(idx_s..idx_s, once(Entry { start, end, value })),
+        // Surrounding a single element, so replace it
+        [it] => {
+            let entry = Entry {
+                start: it.start.min(start),
+                end: it.end.max(end),
+                value,
+            };
+            storage.entries_mut()[idx_s] = entry;
+        }
+        // Surrounding multiple elements, merge them and replace
+        [first, .., last] => {
+            let entry = Entry {
+                start: first.start.min(start),
+                end: last.end.max(end),
+                value,
+            };
+            storage.splice(idx_s..idx_e, once(entry));
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn remove_range<Idx: OrderedIndex, V, S: Storage<Idx, V>>(
+    storage: &mut S,
+    range: RangeInclusive<Idx>,
+    split_boundary_left: impl FnOnce(RangeInclusive<Idx>, &V) -> V,
+    split_boundary_right: impl FnOnce(RangeInclusive<Idx>, &V) -> V,
+) -> Result<(), S::Error> {
+    let (start, end) = range.clone().into_inner();
+    let Range {
+        start: idx_s,
+        end: idx_e,
+    } = overlapping(storage.entries(), range);
+    let (keep_left, keep_right) = match &storage.entries()[idx_s..idx_e] {
+        [] => return Ok(()),
+        [first, .., last] | [first @ last] => (first.start < start, end < last.end),
+    };
+    storage.reserve(idx_e - idx_s, keep_left as usize + keep_right as usize)?;
+
+    let (first, last) = (&storage.entries()[idx_s], &storage.entries()[idx_e - 1]);
+    let left = keep_left.then(|| Entry {
+        start: first.start,
+        end: predecessor(start).unwrap(),
+        value: split_boundary_left(first.range(), &first.value),
+    });
+    let right = keep_right.then(|| Entry {
+        start: successor(end).unwrap(),
+        end: last.end,
+        value: split_boundary_right(last.range(), &last.value),
+    });
+    storage.splice(idx_s..idx_e, [left, right].into_iter().flatten());
+    Ok(())
+}
+
+/// Splits the entry containing `at`, if any, such that the right part starts at `at`.
+pub(crate) fn split_with<Idx: OrderedIndex, V: Clone, S: Storage<Idx, V>>(
+    storage: &mut S,
+    at: Idx,
+    splitter: impl FnOnce(RangeInclusive<Idx>, V) -> (V, V),
+) -> Result<Option<(usize, usize)>, S::Error> {
+    match binary_search(storage.entries(), at) {
+        Within(idx) => split_impl(storage, idx, at, splitter).map(Some),
+        Insert(_) => Ok(None),
+    }
+}
+
+// invariant, `at` is inside the range addressed by idx
+// return value is left range and right range indices of the split range.
+// The indices are the same if the split point was at the start of the range.
+pub(crate) fn split_impl<Idx: OrderedIndex, V: Clone, S: Storage<Idx, V>>(
+    storage: &mut S,
+    idx: usize,
+    at: Idx,
+    splitter: impl FnOnce(RangeInclusive<Idx>, V) -> (V, V),
+) -> Result<(usize, usize), S::Error> {
+    debug_assert!(storage.entries()[idx].range().contains(&at));
+    if storage.entries()[idx].start == at {
+        return Ok((idx, idx));
+    }
+    storage.reserve(0, 1)?;
+    let to_split = &mut storage.entries_mut()[idx];
+    let end = to_split.end;
+    // `to_split.start < at` so this can't underflow
+    to_split.end = predecessor(at).unwrap();
+    // FIXME: The clone should not be necessary here
+    let value = to_split.value.clone();
+    let (left, right) = splitter(to_split.range(), value);
+    to_split.value = left;
+    storage.splice(
+        idx + 1..idx + 1,
+        once(Entry {
+            start: at,
+            end,
+            value: right,
+        }),
+    );
+    Ok((idx, idx + 1))
+}
+
+// endregion
@@ -0,0 +1,122 @@
+//! `Serialize` and `Deserialize` implementations, enabled by the `serde` feature.
+//!
+//! Maps are represented as a sequence of `(start, end, value)` tuples and lists as a sequence of
+//! `(start, end)` tuples, with both bounds inclusive. Deserialization rejects reversed, unsorted
+//! and overlapping ranges.
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use alloc::vec::Vec;
+
+use ::serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use ::serde::ser::{Serialize, SerializeSeq, Serializer};
+
+use crate::map::Entry;
+use crate::{InversionList, InversionMap, OrderedIndex};
+
+#[cfg(test)]
+mod test;
+
+impl<Idx: OrderedIndex + Serialize, V: Serialize> Serialize for InversionMap<Idx, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for Entry { start, end, value } in self.ranges.iter() {
+            seq.serialize_element(&(start, end, value))?;
+        }
+        seq.end()
+    }
+}
+
+impl<Idx: OrderedIndex + Serialize> Serialize for InversionList<Idx> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for Entry { start, end, .. } in self.0.ranges.iter() {
+            seq.serialize_element(&(start, end))?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, Idx, V> Deserialize<'de> for InversionMap<Idx, V>
+where
+    Idx: OrderedIndex + Deserialize<'de>,
+    V: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(EntriesVisitor {
+            expecting: "a sequence of sorted, non-overlapping (start, end, value) tuples",
+            entry: |(start, end, value)| Entry { start, end, value },
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<'de, Idx> Deserialize<'de> for InversionList<Idx>
+where
+    Idx: OrderedIndex + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer
+            .deserialize_seq(EntriesVisitor {
+                expecting: "a sequence of sorted, non-overlapping (start, end) tuples",
+                entry: |(start, end)| Entry {
+                    start,
+                    end,
+                    value: (),
+                },
+                _marker: PhantomData,
+            })
+            .map(InversionList::from_map)
+    }
+}
+
+// Collects the tuples of type `T` into the entries of a map, checking the ranges on the way.
+struct EntriesVisitor<T, Idx, V, F> {
+    expecting: &'static str,
+    entry: F,
+    _marker: PhantomData<fn(T) -> Entry<Idx, V>>,
+}
+
+impl<'de, T, Idx, V, F> Visitor<'de> for EntriesVisitor<T, Idx, V, F>
+where
+    T: Deserialize<'de>,
+    Idx: OrderedIndex,
+    F: Fn(T) -> Entry<Idx, V>,
+{
+    type Value = InversionMap<Idx, V>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(self.expecting)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        // don't trust the hint too much, it comes from the input
+        let mut ranges = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(tuple) = seq.next_element()? {
+            let entry: Entry<Idx, V> = (self.entry)(tuple);
+            if entry.end < entry.start {
+                return Err(de::Error::custom(format_args!(
+                    "range {} ends at {:?} before its start {:?}",
+                    ranges.len(),
+                    entry.end,
+                    entry.start
+                )));
+            }
+            if let Some(prev) = ranges.last().map(|prev: &Entry<Idx, V>| prev.end) {
+                if entry.start <= prev {
+                    return Err(de::Error::custom(format_args!(
+                        "range {} starting at {:?} overlaps or precedes the previous range ending at {:?}",
+                        ranges.len(),
+                        entry.start,
+                        prev
+                    )));
+                }
+            }
+            ranges.push(entry);
+        }
+        Ok(InversionMap {
+            ranges: ranges.into(),
+        })
+    }
+}
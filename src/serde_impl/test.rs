@@ -0,0 +1,49 @@
+use super::*;
+
+use alloc::string::ToString;
+
+#[test]
+fn map_round_trip() {
+    let mut im = InversionMap::<u8, &str>::new();
+    im.insert_range(0..5, "a");
+    im.insert_range(5..=9, "b");
+    im.insert_range(200.., "c");
+    let json = serde_json::to_string(&im).unwrap();
+    assert_eq!(json, r#"[[0,4,"a"],[5,9,"b"],[200,255,"c"]]"#);
+    assert_eq!(
+        serde_json::from_str::<InversionMap<u8, &str>>(&json).unwrap(),
+        im
+    );
+}
+
+#[test]
+fn list_round_trip() {
+    let mut il = InversionList::<u8>::new();
+    il.insert_range(10..20);
+    let il = !il;
+    let json = serde_json::to_string(&il).unwrap();
+    assert_eq!(json, "[[0,9],[20,255]]");
+    assert_eq!(
+        serde_json::from_str::<InversionList<u8>>(&json).unwrap(),
+        il
+    );
+    assert_eq!(
+        serde_json::from_str::<InversionList<u8>>("[]").unwrap(),
+        InversionList::new()
+    );
+}
+
+#[test]
+fn rejects_invalid_ranges() {
+    let err = |json| {
+        serde_json::from_str::<InversionList<u32>>(json)
+            .unwrap_err()
+            .to_string()
+    };
+    assert!(err("[[5,4]]").starts_with("range 0 ends at 4 before its start 5"));
+    assert!(err("[[0,4],[4,8]]")
+        .starts_with("range 1 starting at 4 overlaps or precedes the previous range ending at 4"));
+    assert!(err("[[10,20],[0,4]]").starts_with("range 1 starting at 0 overlaps"));
+    assert!(err(r#"{"start":0}"#).contains("a sequence of sorted, non-overlapping (start, end)"));
+    assert!(serde_json::from_str::<InversionMap<u32, u8>>("[[0,4,1],[2,8,1]]").is_err());
+}
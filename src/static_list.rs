@@ -0,0 +1,275 @@
+//! Read-only inversion lists borrowing their ranges, constructible in `const` and `static` items.
+
+use core::iter::FusedIterator;
+use core::ops::{Range, RangeBounds, RangeInclusive};
+
+#[cfg(feature = "alloc")]
+use crate::map::Entry;
+use crate::util::{bounds_to_range, predecessor, range_from_chunk};
+use crate::OrderedIndex;
+#[cfg(feature = "alloc")]
+use crate::{InversionList, InversionMap};
+
+#[cfg(test)]
+mod test;
+
+use Err as Insert;
+use Ok as Within;
+
+/// A read-only set of ranges borrowed from a sorted slice, typically a precomputed table.
+///
+/// The ranges are either given as a slice of non-empty, sorted and non-overlapping [`Range`]s, or
+/// as a slice of strictly increasing toggle points like the ones of a
+/// [`BoundaryList`](crate::BoundaryList). The constructors check this in debug builds, which
+/// turns a malformed table in a `const` or `static` into a compile error.
+#[derive(Clone, Copy, Debug)]
+pub struct StaticInversionList<'a, Idx: OrderedIndex = u32> {
+    repr: Repr<'a, Idx>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Repr<'a, Idx> {
+    Ranges(&'a [Range<Idx>]),
+    Boundaries(&'a [Idx]),
+}
+
+impl<'a, Idx: OrderedIndex> StaticInversionList<'a, Idx> {
+    /// Creates a list from a slice of ranges without validating it.
+    ///
+    /// The ranges have to be non-empty, sorted and non-overlapping, otherwise queries return
+    /// unspecified results.
+    pub const fn from_ranges_unchecked(ranges: &'a [Range<Idx>]) -> Self {
+        StaticInversionList {
+            repr: Repr::Ranges(ranges),
+        }
+    }
+
+    /// Creates a list from a slice of toggle points without validating it.
+    ///
+    /// The boundaries have to be strictly increasing, otherwise queries return unspecified
+    /// results.
+    pub const fn from_boundaries_unchecked(boundaries: &'a [Idx]) -> Self {
+        StaticInversionList {
+            repr: Repr::Boundaries(boundaries),
+        }
+    }
+}
+
+macro_rules! impl_const_constructors {
+    ($($ty:ty)*) => {
+        $(
+            impl<'a> StaticInversionList<'a, $ty> {
+                /// Creates a list from a slice of non-empty, sorted and non-overlapping ranges.
+                ///
+                /// # Panics
+                ///
+                /// In debug builds, if the ranges violate these requirements.
+                pub const fn from_ranges(ranges: &'a [Range<$ty>]) -> Self {
+                    let mut i = 0;
+                    while i < ranges.len() {
+                        debug_assert!(ranges[i].start < ranges[i].end, "empty range");
+                        debug_assert!(
+                            i == 0 || ranges[i - 1].end <= ranges[i].start,
+                            "ranges are unsorted or overlapping"
+                        );
+                        i += 1;
+                    }
+                    Self::from_ranges_unchecked(ranges)
+                }
+
+                /// Creates a list from a slice of strictly increasing toggle points.
+                ///
+                /// # Panics
+                ///
+                /// In debug builds, if the boundaries are not strictly increasing.
+                pub const fn from_boundaries(boundaries: &'a [$ty]) -> Self {
+                    let mut i = 1;
+                    while i < boundaries.len() {
+                        debug_assert!(
+                            boundaries[i - 1] < boundaries[i],
+                            "boundaries are not strictly increasing"
+                        );
+                        i += 1;
+                    }
+                    Self::from_boundaries_unchecked(boundaries)
+                }
+            }
+        )*
+    };
+}
+
+impl_const_constructors! { u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize char }
+
+impl<'a, Idx: OrderedIndex> StaticInversionList<'a, Idx> {
+    /// Returns the number of ranges in the list.
+    pub const fn len(&self) -> usize {
+        match self.repr {
+            Repr::Ranges(ranges) => ranges.len(),
+            Repr::Boundaries(boundaries) => boundaries.len().div_ceil(2),
+        }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn start(&self) -> Option<Idx> {
+        self.first().map(|range| *range.start())
+    }
+
+    /// Returns the last index covered by the list, this is an inclusive bound.
+    pub fn end(&self) -> Option<Idx> {
+        self.last().map(|range| *range.end())
+    }
+
+    /// Returns the complete surrounding range, if any.
+    pub fn span(&self) -> Option<RangeInclusive<Idx>> {
+        Some(self.start()?..=self.end()?)
+    }
+
+    pub fn first(&self) -> Option<RangeInclusive<Idx>> {
+        self.range_at(0)
+    }
+
+    pub fn last(&self) -> Option<RangeInclusive<Idx>> {
+        self.range_at(self.len().checked_sub(1)?)
+    }
+
+    /// Checks whether the given index is inside any of the contained ranges.
+    pub fn contains(&self, index: Idx) -> bool {
+        self.search(index).is_ok()
+    }
+
+    /// Checks whether this list contains a range that is a "superrange" of the given range.
+    pub fn contains_range<R: RangeBounds<Idx>>(&self, range: R) -> bool {
+        bounds_to_range(range).is_some_and(|range| {
+            self.lookup(*range.start())
+                .is_some_and(|it| it.end() >= range.end())
+        })
+    }
+
+    /// Looks up the range the given index is part of if it is contained within the list.
+    pub fn lookup(&self, index: Idx) -> Option<RangeInclusive<Idx>> {
+        self.range_at(self.search(index).ok()?)
+    }
+
+    /// Looks up all ranges that overlap with the given range.
+    pub fn lookup_range<R: RangeBounds<Idx>>(&self, range: R) -> Option<Iter<'a, Idx>> {
+        let (start, end) = bounds_to_range(range)?.into_inner();
+        let (Within(first) | Insert(first)) = self.search(start);
+        let last = match self.search(end) {
+            Within(idx) => idx + 1,
+            Insert(idx) => idx,
+        };
+        (first < last).then_some(Iter {
+            list: *self,
+            range: first..last,
+        })
+    }
+
+    /// Check if the given range intersects with any ranges inside of the list.
+    pub fn intersects<R: RangeBounds<Idx>>(&self, range: R) -> bool {
+        self.lookup_range(range).is_some()
+    }
+
+    pub fn iter(&self) -> Iter<'a, Idx> {
+        Iter {
+            list: *self,
+            range: 0..self.len(),
+        }
+    }
+
+    /// Copies the ranges into an owned [`InversionList`].
+    #[cfg(feature = "alloc")]
+    pub fn to_list(&self) -> InversionList<Idx> {
+        InversionList::from_map(InversionMap {
+            ranges: self
+                .iter()
+                .map(|range| Entry {
+                    start: *range.start(),
+                    end: *range.end(),
+                    value: (),
+                })
+                .collect(),
+        })
+    }
+
+    /// Returns the index of the range containing `index`, or the index of the first range
+    /// starting after it.
+    fn search(&self, index: Idx) -> Result<usize, usize> {
+        match self.repr {
+            Repr::Ranges(ranges) => {
+                let pos = ranges.partition_point(|range| range.start <= index);
+                match pos.checked_sub(1) {
+                    Some(idx) if index < ranges[idx].end => Within(idx),
+                    _ => Insert(pos),
+                }
+            }
+            Repr::Boundaries(boundaries) => {
+                let pos = boundaries.partition_point(|&b| b <= index);
+                if pos % 2 == 1 {
+                    Within(pos / 2)
+                } else {
+                    Insert(pos / 2)
+                }
+            }
+        }
+    }
+
+    fn range_at(&self, idx: usize) -> Option<RangeInclusive<Idx>> {
+        match self.repr {
+            // ranges are non-empty, so `end > start` and this can't underflow
+            Repr::Ranges(ranges) => ranges
+                .get(idx)
+                .map(|range| range.start..=predecessor(range.end).unwrap()),
+            Repr::Boundaries(boundaries) => range_from_chunk(boundaries.get(idx * 2..)?),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<Idx: OrderedIndex> From<StaticInversionList<'_, Idx>> for InversionList<Idx> {
+    fn from(list: StaticInversionList<'_, Idx>) -> Self {
+        list.to_list()
+    }
+}
+
+impl<'a, Idx: OrderedIndex> IntoIterator for StaticInversionList<'a, Idx> {
+    type Item = RangeInclusive<Idx>;
+    type IntoIter = Iter<'a, Idx>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the ranges of a [`StaticInversionList`].
+#[derive(Clone, Debug)]
+pub struct Iter<'a, Idx: OrderedIndex> {
+    list: StaticInversionList<'a, Idx>,
+    range: Range<usize>,
+}
+
+impl<Idx: OrderedIndex> Iterator for Iter<'_, Idx> {
+    type Item = RangeInclusive<Idx>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.range_at(self.range.next()?)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<Idx: OrderedIndex> DoubleEndedIterator for Iter<'_, Idx> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.range_at(self.range.next_back()?)
+    }
+}
+
+impl<Idx: OrderedIndex> FusedIterator for Iter<'_, Idx> {}
+impl<Idx: OrderedIndex> ExactSizeIterator for Iter<'_, Idx> {}
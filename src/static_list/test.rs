@@ -0,0 +1,78 @@
+use super::*;
+
+static LETTERS: StaticInversionList<'static, char> =
+    StaticInversionList::<char>::from_ranges(&['A'..'[', 'a'..'{']);
+static TOGGLES: StaticInversionList<'static, u8> =
+    StaticInversionList::<u8>::from_boundaries(&[10, 20, 30, 40, 250]);
+
+#[test]
+fn contains() {
+    assert!(LETTERS.contains('A'));
+    assert!(LETTERS.contains('z'));
+    assert!(!LETTERS.contains('['));
+    assert!(!LETTERS.contains('0'));
+    assert!(TOGGLES.contains(10));
+    assert!(!TOGGLES.contains(20));
+    assert!(TOGGLES.contains(u8::MAX));
+    assert!(TOGGLES.contains_range(30..40));
+    assert!(!TOGGLES.contains_range(30..=40));
+}
+
+#[test]
+fn lookup() {
+    assert_eq!(LETTERS.lookup('Q'), Some('A'..='Z'));
+    assert_eq!(LETTERS.lookup('_'), None);
+    assert_eq!(TOGGLES.lookup(35), Some(30..=39));
+    assert_eq!(TOGGLES.lookup(255), Some(250..=255));
+    assert_eq!(TOGGLES.span(), Some(10..=255));
+}
+
+#[test]
+fn lookup_range() {
+    assert!(TOGGLES.lookup_range(20..30).is_none());
+    assert!(TOGGLES.intersects(19..30));
+    assert_eq!(
+        TOGGLES.lookup_range(15..=30).unwrap().collect::<Vec<_>>(),
+        [10..=19, 30..=39]
+    );
+    assert_eq!(
+        TOGGLES.lookup_range(45..).unwrap().collect::<Vec<_>>(),
+        [250..=255]
+    );
+    assert_eq!(
+        LETTERS.lookup_range('Z'..='a').unwrap().collect::<Vec<_>>(),
+        ['A'..='Z', 'a'..='z']
+    );
+}
+
+#[test]
+fn iter_rev() {
+    assert!(LETTERS.iter().rev().eq(['a'..='z', 'A'..='Z']));
+    assert!(TOGGLES.iter().rev().eq([250..=255, 30..=39, 10..=19]));
+    let mut iter = TOGGLES.lookup_range(15..).unwrap();
+    assert_eq!(iter.next_back(), Some(250..=255));
+    assert_eq!(iter.next(), Some(10..=19));
+    assert_eq!(iter.next_back(), Some(30..=39));
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn to_list() {
+    let mut il = InversionList::new();
+    il.insert_range(10..20);
+    il.insert_range(30..40);
+    il.insert_range(250..=255);
+    assert_eq!(TOGGLES.to_list(), il);
+    assert_eq!(
+        InversionList::from(LETTERS),
+        InversionList::from_chars("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz")
+    );
+}
+
+#[test]
+#[should_panic = "ranges are unsorted or overlapping"]
+#[cfg(debug_assertions)]
+fn from_ranges_overlapping() {
+    StaticInversionList::<u32>::from_ranges(&[0..10, 5..15]);
+}
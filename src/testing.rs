@@ -0,0 +1,26 @@
+//! Pseudo random inputs shared by the tests that check the collections against a reference.
+
+use core::ops::RangeInclusive;
+
+use crate::InversionList;
+
+pub(crate) use crate::util::xorshift;
+
+/// Returns a random range starting anywhere and spanning up to `max_len` indices past its start,
+/// cut off at `u8::MAX`.
+pub(crate) fn random_range(seed: &mut u32, max_len: u32) -> RangeInclusive<u8> {
+    let start = (xorshift(seed) % 256) as u8;
+    start..=start.saturating_add((xorshift(seed) % max_len) as u8)
+}
+
+/// Returns a list of a few random ranges along with the bitset of the indices it covers.
+pub(crate) fn random_list(seed: &mut u32) -> (InversionList<u8>, [bool; 256]) {
+    let mut il = InversionList::new();
+    let mut bits = [false; 256];
+    for _ in 0..xorshift(seed) % 12 {
+        let range = random_range(seed, 40);
+        bits[usize::from(*range.start())..=usize::from(*range.end())].fill(true);
+        il.add_range(range);
+    }
+    (il, bits)
+}
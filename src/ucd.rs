@@ -0,0 +1,194 @@
+//! Parsers for the data files of the Unicode Character Database.
+//!
+//! Most UCD files share the line format `XXXX..YYYY ; Value # comment`, which
+//! [`parse_map`] and [`parse_lists`] understand. `UnicodeData.txt` lists one code point per line
+//! with `;` separated fields instead, and marks large ranges with `<..., First>` and
+//! `<..., Last>` lines; it is handled by [`parse_unicode_data`].
+
+use core::fmt;
+use core::ops::RangeInclusive;
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{InversionList, InversionMap};
+
+#[cfg(test)]
+mod test;
+
+/// An error encountered while parsing a UCD file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line the error occurred on, starting at 1.
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A code point was not hexadecimal or lies past `U+10FFFF`.
+    InvalidCodePoint,
+    /// A range ended before it started.
+    ReversedRange,
+    /// A line was missing its value field.
+    MissingField,
+    /// A `<..., First>` line was not directly followed by its `<..., Last>` line, or the other
+    /// way around.
+    UnpairedRange,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self.kind {
+            ParseErrorKind::InvalidCodePoint => "invalid code point",
+            ParseErrorKind::ReversedRange => "range end lies before its start",
+            ParseErrorKind::MissingField => "missing field",
+            ParseErrorKind::UnpairedRange => "unpaired First/Last range",
+        };
+        write!(f, "{} on line {}", msg, self.line)
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+/// Parses a file in the `XXXX..YYYY ; Value # comment` format into a map from code points to
+/// their value.
+///
+/// Values consisting of several fields are kept with their fields joined by `"; "`. Later lines
+/// overwrite earlier ones, so files assigning multiple properties to the same code points, like
+/// `DerivedCoreProperties.txt`, are better parsed with [`parse_lists`].
+pub fn parse_map(src: &str) -> Result<InversionMap<u32, String>, ParseError> {
+    let mut map = InversionMap::new();
+    for line in lines(src) {
+        let (range, value) = line?;
+        map.insert_range(range, value);
+    }
+    Ok(map)
+}
+
+/// Parses a file in the `XXXX..YYYY ; Value # comment` format into one list of code points per
+/// value.
+pub fn parse_lists(src: &str) -> Result<BTreeMap<String, InversionList<u32>>, ParseError> {
+    let mut ranges = BTreeMap::<String, Vec<RangeInclusive<u32>>>::new();
+    for line in lines(src) {
+        let (range, value) = line?;
+        ranges.entry(value).or_default().push(range);
+    }
+    Ok(ranges
+        .into_iter()
+        .map(|(value, ranges)| (value, ranges.into_iter().collect()))
+        .collect())
+}
+
+/// Parses `UnicodeData.txt` into a map from code points to the value of their `field`th field,
+/// where field 0 is the code point itself, 1 the name, 2 the general category and so on.
+///
+/// Code points with an empty field are left out. Ranges given by `<..., First>` and
+/// `<..., Last>` lines are inserted as a whole, with their name field shortened to `<...>`.
+pub fn parse_unicode_data(
+    src: &str,
+    field: usize,
+) -> Result<InversionMap<u32, String>, ParseError> {
+    let mut map = InversionMap::new();
+    // the code point and value of a `<..., First>` line waiting for its `<..., Last>`
+    let mut first: Option<(u32, String)> = None;
+    for (line, text) in src.lines().enumerate() {
+        let line = line + 1;
+        let error = |kind| ParseError { line, kind };
+        let text = strip_comment(text);
+        if text.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = text.split(';').map(str::trim).collect();
+        let cp = parse_code_point(fields[0]).ok_or(error(ParseErrorKind::InvalidCodePoint))?;
+        let name = *fields.get(1).ok_or(error(ParseErrorKind::MissingField))?;
+        let value = *fields
+            .get(field)
+            .ok_or(error(ParseErrorKind::MissingField))?;
+
+        let (start, value) = if let Some(range) = name.strip_suffix(", First>") {
+            if first.is_some() {
+                return Err(error(ParseErrorKind::UnpairedRange));
+            }
+            let value = if field == 1 {
+                range.to_string() + ">"
+            } else {
+                value.to_string()
+            };
+            first = Some((cp, value));
+            continue;
+        } else if name.ends_with(", Last>") {
+            first.take().ok_or(error(ParseErrorKind::UnpairedRange))?
+        } else if first.is_some() {
+            return Err(error(ParseErrorKind::UnpairedRange));
+        } else {
+            (cp, value.to_string())
+        };
+        if start > cp {
+            return Err(error(ParseErrorKind::ReversedRange));
+        }
+        if !value.is_empty() {
+            map.insert_range(start..=cp, value);
+        }
+    }
+    match first {
+        Some(_) => Err(ParseError {
+            line: src.lines().count(),
+            kind: ParseErrorKind::UnpairedRange,
+        }),
+        None => Ok(map),
+    }
+}
+
+/// Yields the range and value of every data line.
+fn lines(
+    src: &str,
+) -> impl Iterator<Item = Result<(RangeInclusive<u32>, String), ParseError>> + '_ {
+    src.lines().enumerate().filter_map(|(line, text)| {
+        let text = strip_comment(text);
+        (!text.is_empty()).then(|| {
+            parse_line(text).map_err(|kind| ParseError {
+                line: line + 1,
+                kind,
+            })
+        })
+    })
+}
+
+fn parse_line(text: &str) -> Result<(RangeInclusive<u32>, String), ParseErrorKind> {
+    let (range, value) = text.split_once(';').ok_or(ParseErrorKind::MissingField)?;
+    let (start, end) = match range.trim().split_once("..") {
+        Some((start, end)) => (start, end),
+        None => (range.trim(), range.trim()),
+    };
+    let start = parse_code_point(start).ok_or(ParseErrorKind::InvalidCodePoint)?;
+    let end = parse_code_point(end).ok_or(ParseErrorKind::InvalidCodePoint)?;
+    if start > end {
+        return Err(ParseErrorKind::ReversedRange);
+    }
+    let mut fields = value.split(';').map(str::trim);
+    let mut value = fields.next().unwrap_or_default().to_string();
+    if value.is_empty() {
+        return Err(ParseErrorKind::MissingField);
+    }
+    for field in fields {
+        value.push_str("; ");
+        value.push_str(field);
+    }
+    Ok((start..=end, value))
+}
+
+fn strip_comment(text: &str) -> &str {
+    text.split_once('#').map_or(text, |(data, _)| data).trim()
+}
+
+fn parse_code_point(text: &str) -> Option<u32> {
+    let text = text.trim();
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(text, 16)
+        .ok()
+        .filter(|&cp| cp <= 0x10FFFF)
+}
@@ -0,0 +1,116 @@
+use super::*;
+
+const SCRIPTS: &str = "\
+# Scripts-15.1.0.txt
+
+0000..001F    ; Common # Cc  [32] <control-0000>..<control-001F>
+0020          ; Common # Zs       SPACE
+0041..005A    ; Latin # L&  [26] LATIN CAPITAL LETTER A..LATIN CAPITAL LETTER Z
+
+0061..007A    ; Latin # L&  [26] LATIN SMALL LETTER A..LATIN SMALL LETTER Z
+0370..0373    ; Greek # L&   [4] GREEK CAPITAL LETTER HETA..GREEK SMALL LETTER ARCHAIC SAMPI
+";
+
+#[test]
+fn map() {
+    let map = parse_map(SCRIPTS).unwrap();
+    assert_eq!(map.lookup(0x20), Some((0x20..=0x20, &"Common".to_string())));
+    assert_eq!(map.lookup(0x71), Some((0x61..=0x7A, &"Latin".to_string())));
+    assert_eq!(map.lookup(0x30), None);
+    assert_eq!(map.len(), 5);
+}
+
+#[test]
+fn lists() {
+    let lists = parse_lists(SCRIPTS).unwrap();
+    assert_eq!(
+        lists.keys().map(String::as_str).collect::<Vec<_>>(),
+        ["Common", "Greek", "Latin"]
+    );
+    assert_eq!(lists["Common"].iter().collect::<Vec<_>>(), [0x0..=0x20]);
+    assert_eq!(
+        lists["Latin"].iter().collect::<Vec<_>>(),
+        [0x41..=0x5A, 0x61..=0x7A]
+    );
+}
+
+#[test]
+fn multiple_fields() {
+    let map = parse_map("094D ; InCB; Linker # Mn DEVANAGARI SIGN VIRAMA").unwrap();
+    assert_eq!(map.lookup(0x94D).unwrap().1, "InCB; Linker");
+}
+
+#[test]
+fn errors() {
+    let error = |line, kind| ParseError { line, kind };
+    assert_eq!(
+        parse_map("# header\n0041..0030 ; Latin").unwrap_err(),
+        error(2, ParseErrorKind::ReversedRange)
+    );
+    assert_eq!(
+        parse_map("0041..zz ; Latin").unwrap_err(),
+        error(1, ParseErrorKind::InvalidCodePoint)
+    );
+    assert_eq!(
+        parse_map("110000 ; Nope").unwrap_err(),
+        error(1, ParseErrorKind::InvalidCodePoint)
+    );
+    assert_eq!(
+        parse_lists("0041 ;").unwrap_err(),
+        error(1, ParseErrorKind::MissingField)
+    );
+    assert_eq!(
+        parse_lists("0041").unwrap_err(),
+        error(1, ParseErrorKind::MissingField)
+    );
+}
+
+const UNICODE_DATA: &str = "\
+0041;LATIN CAPITAL LETTER A;Lu;0;L;;;;;N;;;;0061;
+0042;LATIN CAPITAL LETTER B;Lu;0;L;;;;;N;;;;0062;
+3400;<CJK Ideograph Extension A, First>;Lo;0;L;;;;;N;;;;;
+4DBF;<CJK Ideograph Extension A, Last>;Lo;0;L;;;;;N;;;;;
+";
+
+#[test]
+fn unicode_data() {
+    let categories = parse_unicode_data(UNICODE_DATA, 2).unwrap();
+    assert_eq!(
+        categories
+            .iter()
+            .map(|(r, v)| (r, v.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            (0x41..=0x41, "Lu"),
+            (0x42..=0x42, "Lu"),
+            (0x3400..=0x4DBF, "Lo")
+        ]
+    );
+    let names = parse_unicode_data(UNICODE_DATA, 1).unwrap();
+    assert_eq!(
+        names.lookup(0x4000).unwrap().1,
+        "<CJK Ideograph Extension A>"
+    );
+    let lowercase = parse_unicode_data(UNICODE_DATA, 13).unwrap();
+    assert_eq!(lowercase.len(), 2);
+    assert_eq!(lowercase.lookup(0x42).unwrap().1, "0062");
+}
+
+#[test]
+fn unicode_data_unpaired() {
+    let src = "3400;<CJK Ideograph Extension A, First>;Lo;0;L;;;;;N;;;;;\n0041;A;Lu";
+    assert_eq!(
+        parse_unicode_data(src, 2),
+        Err(ParseError {
+            line: 2,
+            kind: ParseErrorKind::UnpairedRange
+        })
+    );
+    assert_eq!(
+        parse_unicode_data(&src[..src.find('\n').unwrap()], 2),
+        Err(ParseError {
+            line: 1,
+            kind: ParseErrorKind::UnpairedRange
+        })
+    );
+}
@@ -1,4 +1,4 @@
-use core::ops::{Bound, Range, RangeBounds};
+use core::ops::{Bound, RangeBounds, RangeInclusive};
 
 use crate::OrderedIndex;
 
@@ -14,22 +14,88 @@ pub(crate) mod variance {
     pub(crate) type InvariantLifetime<'lt>     = Invariant<&'lt ()>;
 }
 
-/// Turn a [`RangeBounds`] into a [`Range`], unless the resulting range is empty.
-pub(crate) fn bounds_to_range<T: OrderedIndex, R: RangeBounds<T>>(range: R) -> Option<Range<T>> {
+/// Returns the index directly following `idx`, unless `idx` is the maximum value.
+#[inline]
+pub(crate) fn successor<T: OrderedIndex>(idx: T) -> Option<T> {
+    idx.checked_add(T::one())
+}
+
+/// Returns the index directly preceding `idx`, unless `idx` is the minimum value.
+#[inline]
+pub(crate) fn predecessor<T: OrderedIndex>(idx: T) -> Option<T> {
+    idx.checked_sub(T::one())
+}
+
+/// Returns the index `n` steps after `idx`, unless that overflows.
+#[cfg(feature = "alloc")]
+pub(crate) fn advance<T: OrderedIndex>(mut idx: T, mut n: u128) -> Option<T> {
+    // powers of two steps, the largest ones might not be representable in `T`
+    let mut steps = [(T::one(), 1u128); 128];
+    let mut len = 1;
+    while len < 128 {
+        let (step, size) = steps[len - 1];
+        match step.checked_add(step) {
+            Some(double) if size << 1 <= n => steps[len] = (double, size << 1),
+            _ => break,
+        }
+        len += 1;
+    }
+    for &(step, size) in steps[..len].iter().rev() {
+        while n >= size {
+            idx = idx.checked_add(step)?;
+            n -= size;
+        }
+    }
+    Some(idx)
+}
+
+/// Advances the xorshift32 generator in `seed` by one step and returns the new state.
+#[cfg(feature = "alloc")]
+pub(crate) fn xorshift(seed: &mut u32) -> u32 {
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 17;
+    *seed ^= *seed << 5;
+    *seed
+}
+
+/// Returns the number of indices in `range`, saturating at `u128::MAX` for the full domain of
+/// 128-bit types.
+#[inline]
+pub(crate) fn range_count<T: OrderedIndex>(range: &RangeInclusive<T>) -> u128 {
+    T::steps_between(*range.start(), *range.end())
+        .unwrap()
+        .saturating_add(1)
+}
+
+/// Turn a [`RangeBounds`] into a [`RangeInclusive`], unless the resulting range is empty.
+pub(crate) fn bounds_to_range<T: OrderedIndex, R: RangeBounds<T>>(
+    range: R,
+) -> Option<RangeInclusive<T>> {
     let start = match range.start_bound() {
         Bound::Included(&n) => n,
-        Bound::Excluded(&n) => n.checked_add(T::one())?,
+        Bound::Excluded(&n) => successor(n)?,
         Bound::Unbounded => T::min_value(),
     };
     let end = match range.end_bound() {
-        Bound::Included(&n) => n.checked_add(T::one()).expect("range end bound overflowed"),
-        Bound::Excluded(&n) => n,
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => predecessor(n)?,
         Bound::Unbounded => T::max_value(),
     };
 
-    if end <= start {
+    if end < start {
         None
     } else {
-        Some(start..end)
+        Some(start..=end)
+    }
+}
+
+/// Turns the first two toggle points of a boundary slice into the range they delimit, a lone
+/// boundary starts a range reaching up to [`OrderedIndex::max_value()`].
+pub(crate) fn range_from_chunk<Idx: OrderedIndex>(chunk: &[Idx]) -> Option<RangeInclusive<Idx>> {
+    match *chunk {
+        [] => None,
+        [start] => Some(start..=Idx::max_value()),
+        // boundaries are strictly increasing, so `end > start` and this can't underflow
+        [start, end, ..] => Some(start..=predecessor(end).unwrap()),
     }
 }